@@ -3,16 +3,41 @@ use iced::{
   Alignment, Element, Length, Pixels,
   alignment::Vertical,
   widget::{
-    button, checkbox, column, combo_box, image, row, scrollable, slider, space, text, text_input,
+    button, checkbox, column, combo_box, container, image, row, scrollable, slider, space, text,
+    text_input,
   },
 };
 use yast_core::{
   layout::{component::Component, settings::SettingsValue},
-  lua::settings::{SettingsFactoryEntryContent, SettingsFactoryValue},
+  lua::{
+    sandbox::capability_description,
+    settings::{SettingsFactoryEntryContent, SettingsFactoryValue},
+  },
 };
 
 use crate::{App, AppMessage};
 
+/// capabilities a component requires, shown before adding it
+pub fn capabilities_view<'a>(capabilities: &[String]) -> Element<'a, AppMessage> {
+  if capabilities.is_empty() {
+    return text("Requires no capabilities").size(12.).into();
+  }
+
+  let mut column_vec = vec![text("Requires capabilities:").size(12.).into()];
+  for capability in capabilities {
+    column_vec.push(
+      text(format!(
+        "- {}: {}",
+        capability,
+        capability_description(capability).unwrap_or("unknown capability")
+      ))
+      .size(12.)
+      .into(),
+    );
+  }
+  column(column_vec).into()
+}
+
 pub fn component_editor<'a>(
   state: &'a App,
   p: &Component,
@@ -92,6 +117,34 @@ pub fn component_editor<'a>(
       .into(),
     );
 
+    if state.new_component_combo_box_selected.is_some() {
+      column_vec.push(
+        container(capabilities_view(&state.new_component_capabilities))
+          .padding(5.0)
+          .into(),
+      );
+    }
+
+    let moved_full_path = full_path.clone();
+    column_vec.push(
+      row(vec![
+        text("Timer").into(),
+        space().width(Length::Fixed(5.0)).into(),
+        text_input("Timer", &format!("{}", p.timer + 1))
+          .on_input(move |new| AppMessage::ModifyComponentTimer(moved_full_path.clone(), new))
+          .into(),
+      ])
+      .align_y(Vertical::Center)
+      .padding(5.0)
+      .into(),
+    );
+
+    column_vec.push(
+      container(capabilities_view(&p.capabilities))
+        .padding(5.0)
+        .into(),
+    );
+
     column_vec.push(
       text("-- Parameters --")
         .width(Length::Fill)
@@ -6,10 +6,13 @@ use livesplit_core::{Run, Segment, Timer};
 use strum::IntoEnumIterator;
 use yast_core::{
   defaults::copy_default_components,
-  layout::{HotkeyAction, Layout, component::Component, settings::SettingsValue},
+  layout::{
+    DEFAULT_MAX_REFRESH_RATE, HotkeyAction, IDLE_REFRESH_RATE, Layout, component::Component,
+    settings::SettingsValue,
+  },
   lua::{
     LuaContext,
-    inject::inject_values_in_lua,
+    inject::{inject_frame_in_lua, inject_values_in_lua},
     settings::{SettingsFactoryEntryContent, SettingsFactoryValue},
   },
   repository::Repository,
@@ -19,7 +22,7 @@ use yast_core::{
 extern crate log;
 
 use crate::{
-  editor::component_editor,
+  editor::{capabilities_view, component_editor},
   tree::{build_tree_from_layout_part, get_mut_component_at_path},
 };
 #[cfg(target_os = "windows")]
@@ -36,6 +39,7 @@ use std::{
   backtrace::BacktraceStatus,
   collections::HashMap,
   fs::{self, read_to_string},
+  path::Path,
   time::{Duration, SystemTime},
 };
 
@@ -64,10 +68,13 @@ pub struct App {
   pub opened_component: Vec<usize>,
   pub new_component_combo_box_state: combo_box::State<String>,
   pub new_component_combo_box_selected: Option<String>,
+  /// capabilities required by the selected component
+  pub new_component_capabilities: Vec<String>,
   pub parameter_options_combo_box_states: HashMap<String, combo_box::State<String>>,
 
   pub keyboard_listener: KeyboardListener,
   pub hotkey_recorder: Option<HotkeyAction>,
+  pub hotkey_timer: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -86,6 +93,7 @@ pub enum AppMessage {
 
   LayoutNameChanged(String),
   LayoutAuthorChanged(String),
+  LayoutMaxRefreshRateChanged(String),
 
   OpenComponent(Vec<usize>),
 
@@ -95,6 +103,7 @@ pub enum AppMessage {
 
   MoveComponentUp(Vec<usize>),
   MoveComponentDown(Vec<usize>),
+  ModifyComponentTimer(Vec<usize>, String),
 
   ModifyParameterBoolean(Vec<usize>, String, bool),
   ModifyParameterString(Vec<usize>, String, String),
@@ -105,6 +114,7 @@ pub enum AppMessage {
   ModifyParameterImageOpen(Vec<usize>, String),
   ModifyParameterImageSubmit(Vec<usize>, String, Vec<u8>),
 
+  SelectHotkeyTimer(usize),
   StartRecordingHotkey(HotkeyAction),
   AssignHotkey(Hotkey),
 }
@@ -156,10 +166,12 @@ impl App {
         opened_component: Vec::new(),
         new_component_combo_box_state: combo_box::State::new(new_component_options),
         new_component_combo_box_selected: None,
+        new_component_capabilities: Vec::new(),
         parameter_options_combo_box_states: HashMap::new(),
 
         keyboard_listener: KeyboardListener::new().expect("couldn't start keyboard listener"),
         hotkey_recorder: None,
+        hotkey_timer: 0,
       },
       Task::none(),
     )
@@ -189,29 +201,33 @@ impl App {
   fn update(&mut self, message: AppMessage) -> Result<Task<AppMessage>> {
     match message {
       AppMessage::Update => {
+        if self.hotkey_recorder.is_none() {
+          // keys pressed while not recording would otherwise be recorded later
+          while self.keyboard_listener.try_recv().is_some() {}
+          return Ok(Task::none());
+        }
+
         if let Some(event) = self.keyboard_listener.try_recv() {
-          if self.hotkey_recorder.is_some() {
-            if !event.is_key_down {
-              let key = event.key;
-              let mut modifiers = Modifiers::empty();
-              for modifier in event.modifiers {
-                match modifier {
-                  Modifiers::CMD_LEFT => modifiers.insert(Modifiers::CMD),
-                  Modifiers::CMD_RIGHT => modifiers.insert(Modifiers::CMD),
-                  Modifiers::CTRL_LEFT => modifiers.insert(Modifiers::CTRL),
-                  Modifiers::CTRL_RIGHT => modifiers.insert(Modifiers::CTRL),
-                  Modifiers::OPT_LEFT => modifiers.insert(Modifiers::OPT),
-                  Modifiers::OPT_RIGHT => modifiers.insert(Modifiers::OPT),
-                  Modifiers::SHIFT_LEFT => modifiers.insert(Modifiers::SHIFT),
-                  Modifiers::SHIFT_RIGHT => modifiers.insert(Modifiers::SHIFT),
-                  other => modifiers.insert(other),
-                }
+          if !event.is_key_down {
+            let key = event.key;
+            let mut modifiers = Modifiers::empty();
+            for modifier in event.modifiers {
+              match modifier {
+                Modifiers::CMD_LEFT => modifiers.insert(Modifiers::CMD),
+                Modifiers::CMD_RIGHT => modifiers.insert(Modifiers::CMD),
+                Modifiers::CTRL_LEFT => modifiers.insert(Modifiers::CTRL),
+                Modifiers::CTRL_RIGHT => modifiers.insert(Modifiers::CTRL),
+                Modifiers::OPT_LEFT => modifiers.insert(Modifiers::OPT),
+                Modifiers::OPT_RIGHT => modifiers.insert(Modifiers::OPT),
+                Modifiers::SHIFT_LEFT => modifiers.insert(Modifiers::SHIFT),
+                Modifiers::SHIFT_RIGHT => modifiers.insert(Modifiers::SHIFT),
+                other => modifiers.insert(other),
               }
-
-              return Ok(Task::done(AppMessage::AssignHotkey(Hotkey::new(
-                modifiers, key,
-              )?)));
             }
+
+            return Ok(Task::done(AppMessage::AssignHotkey(Hotkey::new(
+              modifiers, key,
+            )?)));
           }
         }
 
@@ -286,12 +302,13 @@ impl App {
         Ok(future)
       }
       AppMessage::LoadLayout(path) => {
-        let toml_string = read_to_string(path)?;
+        let toml_string = read_to_string(&path)?;
         let new_layout = Layout::load(
           &mut self.repository,
           &self.components,
           &self.lua_context.lua,
           toml_string,
+          Some(Path::new(&path)),
         )?;
         self.layout = new_layout;
         info!(
@@ -343,10 +360,20 @@ impl App {
         self.layout.author = n;
         Ok(Task::none())
       }
+      AppMessage::LayoutMaxRefreshRateChanged(n) => {
+        if let Ok(parsed) = n.parse::<f64>() {
+          if parsed.is_finite() && parsed > 0. {
+            self.layout.max_refresh_rate = parsed;
+            self.layout_edited = true;
+          }
+        }
+        Ok(Task::none())
+      }
       AppMessage::OpenComponent(n) => {
         if let Some(lcontent) = &mut self.layout.content {
           self.opened_component = n.clone();
           self.new_component_combo_box_selected = None;
+          self.new_component_capabilities.clear();
           self.parameter_options_combo_box_states.clear();
 
           let comp = get_mut_component_at_path(lcontent, n)?;
@@ -367,7 +394,17 @@ impl App {
         Ok(Task::none())
       }
       AppMessage::NewComponentComboBoxSelected(n) => {
-        self.new_component_combo_box_selected = Some(n);
+        self.new_component_capabilities.clear();
+        self.new_component_combo_box_selected = Some(n.clone());
+        let component = Component::from_str(
+          self
+            .components
+            .get(&n)
+            .ok_or(anyhow::Error::msg("couldn't find component in factories"))?
+            .clone(),
+          &self.lua_context.lua,
+        )?;
+        self.new_component_capabilities = component.capabilities;
         Ok(Task::none())
       }
       AppMessage::AddNewComponent(path, name) => {
@@ -431,6 +468,17 @@ impl App {
         self.layout_edited = true;
         Ok(Task::done(AppMessage::OpenComponent(new_pos)))
       }
+      AppMessage::ModifyComponentTimer(path, value) => {
+        if let Some(lcontent) = &mut self.layout.content {
+          if let Ok(parsed) = value.parse::<usize>() {
+            if parsed > 0 {
+              get_mut_component_at_path(lcontent, path)?.timer = parsed - 1;
+              self.layout_edited = true;
+            }
+          }
+        }
+        Ok(Task::none())
+      }
       AppMessage::ModifyParameterBoolean(path, param, value) => {
         let comp_settings = self
           .layout
@@ -550,6 +598,11 @@ impl App {
         self.layout_edited = true;
         Ok(Task::none())
       }
+      AppMessage::SelectHotkeyTimer(timer) => {
+        self.hotkey_timer = timer;
+        self.hotkey_recorder = None;
+        Ok(Task::none())
+      }
       AppMessage::StartRecordingHotkey(action) => {
         self.hotkey_recorder = Some(action);
         Ok(Task::none())
@@ -563,10 +616,11 @@ impl App {
         }
 
         if let Some(action) = &self.hotkey_recorder {
+          let hotkeys = self.layout.timer_hotkeys_mut(self.hotkey_timer);
           if is_escape {
-            self.layout.hotkeys.remove(action);
+            hotkeys.remove(action);
           } else {
-            self.layout.hotkeys.insert(action.clone(), hotkey);
+            hotkeys.insert(action.clone(), hotkey);
           }
         }
 
@@ -597,6 +651,12 @@ impl App {
         text_input("Layout Author", &self.layout.author)
           .on_input(|i| AppMessage::LayoutAuthorChanged(i))
           .into(),
+        text_input(
+          "Max Refresh Rate",
+          &format!("{}", self.layout.max_refresh_rate),
+        )
+        .on_input(|i| AppMessage::LayoutMaxRefreshRateChanged(i))
+        .into(),
       ])
       .padding(5.0)
       .spacing(5.0)
@@ -622,25 +682,21 @@ impl App {
 
     match self.screen {
       AppScreen::LayoutPreview => {
-        inject_values_in_lua(&self.lua_context.lua, &self.dummy_timer, &self.repository)
-          .unwrap_or_else(|err| error!("couldn't inject values into lua: {}", err));
+        inject_values_in_lua(
+          &self.lua_context.lua,
+          &[(&self.dummy_timer, &self.repository)],
+        )
+        .unwrap_or_else(|err| error!("couldn't inject values into lua: {}", err));
+        inject_frame_in_lua(&self.lua_context.lua)
+          .unwrap_or_else(|err| error!("couldn't inject frame clock into lua: {}", err));
 
         let inner = if let Some(lcontent) = &self.layout.content {
-          lcontent
-            .build(
-              &self.lua_context.lua,
-              vec![],
-              &self.layout.settings,
-              &self.repository,
-            )
-            .unwrap_or_else(|err| {
-              error!("couldn't build layout: {}", err);
-              text("couldn't build layout, please check the logs for full details")
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .center()
-                .into()
-            })
+          lcontent.build(
+            &self.lua_context.lua,
+            vec![],
+            &self.layout.settings,
+            &self.repository,
+          )
         } else {
           space().width(Length::Fill).height(Length::Fill).into()
         };
@@ -710,6 +766,10 @@ impl App {
                   .into(),
               ])
               .into(),
+              match self.new_component_combo_box_selected {
+                Some(_) => capabilities_view(&self.new_component_capabilities),
+                None => space().into(),
+              },
             ])
             .height(Length::Fill)
             .padding(5.0)
@@ -718,6 +778,24 @@ impl App {
         }
       }
       AppScreen::HotkeyEditor => {
+        let mut timers_row_vec = vec![text("Timer").into()];
+        for timer in 0..=self.layout.extra_hotkeys.len() + 1 {
+          let mut timer_button =
+            button(text(format!("{}", timer + 1))).on_press(AppMessage::SelectHotkeyTimer(timer));
+          if timer != self.hotkey_timer {
+            timer_button = timer_button.style(button::secondary);
+          }
+          timers_row_vec.push(timer_button.into());
+        }
+        main_column_vec.push(
+          row(timers_row_vec)
+            .align_y(Vertical::Center)
+            .spacing(5.0)
+            .padding(Padding::new(5.0))
+            .into(),
+        );
+
+        let hotkeys = self.layout.timer_hotkeys(self.hotkey_timer);
         for action in HotkeyAction::iter() {
           let mut row_vec = Vec::new();
 
@@ -729,7 +807,7 @@ impl App {
               .into(),
           );
 
-          if let Some(hotkey) = self.layout.hotkeys.get(&action.clone()) {
+          if let Some(hotkey) = hotkeys.and_then(|h| h.get(&action.clone())) {
             let hotkey_string = hotkey.to_string();
             row_vec.push(text(hotkey_string).into())
           }
@@ -758,11 +836,19 @@ impl App {
     format!("YASLE {}", VERSION)
   }
 
+  /// update rate, only high while a hotkey is being recorded, as the dummy timer never runs
+  fn refresh_rate(&self) -> f64 {
+    match self.hotkey_recorder {
+      Some(_) => DEFAULT_MAX_REFRESH_RATE,
+      None => IDLE_REFRESH_RATE,
+    }
+  }
+
   #[cfg(not(target_os = "windows"))]
   fn subscription(&self) -> Subscription<AppMessage> {
     Subscription::batch(vec![
       window::close_requests().map(AppMessage::WindowClosing),
-      every(Duration::from_secs_f64(1.0 / 60.0)).map(|_| AppMessage::Update),
+      every(Duration::from_secs_f64(1.0 / self.refresh_rate())).map(|_| AppMessage::Update),
     ])
   }
 
@@ -771,7 +857,7 @@ impl App {
     Subscription::batch(vec![
       window::close_events().map(AppMessage::WindowClosing),
       keyboard::listen().map(AppMessage::KeyboardEvent),
-      every(Duration::from_secs_f64(1.0 / 60.0)).map(|_| AppMessage::Update),
+      every(Duration::from_secs_f64(1.0 / self.refresh_rate())).map(|_| AppMessage::Update),
     ])
   }
 }
@@ -0,0 +1,73 @@
+//! compares rebuilding every injected table each frame with the cached injection
+//!
+//! run with `cargo bench -p yast-core --bench inject`
+
+use std::time::{Duration, Instant};
+
+use livesplit_core::{Run, Segment, Timer};
+use mlua::prelude::*;
+use yast_core::{
+  lua::inject::{inject_values_in_lua, invalidate_value_cache},
+  repository::Repository,
+};
+
+const SEGMENTS: usize = 150;
+const ATTEMPTS: usize = 20;
+const FRAMES: u32 = 200;
+
+/// timer halfway through a run with some history
+fn timer() -> Timer {
+  let mut run = Run::new();
+  run.set_game_name("Benchmark");
+  run.set_category_name("Any%");
+  for i in 0..SEGMENTS {
+    run.push_segment(Segment::new(format!("Segment {}", i + 1)));
+  }
+
+  let mut timer = Timer::new(run).expect("couldn't create timer");
+  for _ in 0..ATTEMPTS {
+    timer.start();
+    for _ in 0..SEGMENTS {
+      timer.split();
+    }
+    timer.reset(true);
+  }
+  timer.start();
+  for _ in 0..SEGMENTS / 2 {
+    timer.split();
+  }
+  timer
+}
+
+fn measure(lua: &Lua, timer: &Timer, repository: &Repository, invalidate: bool) -> Duration {
+  let start = Instant::now();
+  for _ in 0..FRAMES {
+    if invalidate {
+      invalidate_value_cache(lua);
+    }
+    inject_values_in_lua(lua, &[(timer, repository)]).expect("couldn't inject values");
+  }
+  start.elapsed() / FRAMES
+}
+
+fn main() {
+  let lua = Lua::new();
+  let timer = timer();
+  let mut repository = Repository::default();
+  repository
+    .update_from_splits(timer.run())
+    .expect("couldn't build repository");
+
+  // warm up, and fill the cache
+  inject_values_in_lua(&lua, &[(&timer, &repository)]).expect("couldn't inject values");
+
+  let full = measure(&lua, &timer, &repository, true);
+  let incremental = measure(&lua, &timer, &repository, false);
+
+  println!(
+    "{} segments, {} attempts, {} frames",
+    SEGMENTS, ATTEMPTS, FRAMES
+  );
+  println!("full rebuild: {:?} per frame", full);
+  println!("incremental:  {:?} per frame", incremental);
+}
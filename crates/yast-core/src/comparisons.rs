@@ -0,0 +1,246 @@
+//! comparison management: livesplit-core generators and lua-defined comparisons
+
+use std::{
+  fs::{read_dir, read_to_string},
+  path::Path,
+};
+
+use anyhow::Result;
+use livesplit_core::{
+  Run, Time, TimeSpan,
+  comparison::{
+    ComparisonGenerator, average_segments::AverageSegments, balanced_pb::BalancedPB,
+    latest_run::LatestRun, median_segments::MedianSegments, worst_segments::WorstSegments,
+  },
+};
+use log::error;
+use mlua::prelude::*;
+
+use crate::lua::{
+  budget::run_with_budget,
+  sandbox::{comparison_folder, environment, grant_capabilities},
+};
+
+/// names of the generators that can be added or removed from a run
+pub const OPTIONAL_GENERATORS: [&str; 5] = [
+  AverageSegments::NAME,
+  MedianSegments::NAME,
+  LatestRun::NAME,
+  BalancedPB::NAME,
+  WorstSegments::NAME,
+];
+
+fn generator_from_name(name: &str) -> Option<Box<dyn ComparisonGenerator>> {
+  match name {
+    AverageSegments::NAME => Some(Box::new(AverageSegments)),
+    MedianSegments::NAME => Some(Box::new(MedianSegments)),
+    LatestRun::NAME => Some(Box::new(LatestRun)),
+    BalancedPB::NAME => Some(Box::new(BalancedPB)),
+    WorstSegments::NAME => Some(Box::new(WorstSegments)),
+    _ => None,
+  }
+}
+
+pub fn has_generator(run: &Run, name: &str) -> bool {
+  run.comparison_generators().iter().any(|g| g.name() == name)
+}
+
+/// adds the generator to the run if it's missing, removes it otherwise
+pub fn toggle_generator(run: &mut Run, name: &str) -> Result<()> {
+  if has_generator(run, name) {
+    run.comparison_generators_mut().retain(|g| g.name() != name);
+  } else {
+    let generator = generator_from_name(name).ok_or(anyhow::Error::msg(format!(
+      "unknown comparison generator: {}",
+      name
+    )))?;
+    run.comparison_generators_mut().push(generator);
+  }
+  run.regenerate_comparisons();
+  Ok(())
+}
+
+/// comparison whose split times are generated by a lua script
+///
+/// generated comparisons are stored as custom comparisons in the run, so they are saved with the splits
+#[derive(Clone)]
+pub struct LuaComparison {
+  pub name: String,
+  generate: LuaFunction,
+}
+
+impl LuaComparison {
+  /// loads a lua comparison from its source, in a sandboxed environment like components
+  pub fn from_str(s: String, lua: &Lua) -> Result<Self> {
+    let env = environment(lua)?;
+    let t = run_with_budget(lua, || {
+      lua.load(s).set_environment(env.clone()).eval::<LuaTable>()
+    })?;
+
+    let name = t.get::<String>("name")?;
+    let capabilities = t
+      .get::<Option<Vec<String>>>("capabilities")?
+      .unwrap_or_default();
+    grant_capabilities(lua, &env, &name, comparison_folder(&name)?, &capabilities)?;
+
+    Ok(Self {
+      name,
+      generate: t.get("generate")?,
+    })
+  }
+
+  /// loads every lua comparison of a directory
+  ///
+  /// comparisons failing to load, or exceeding their time budget while loading, are skipped
+  pub fn import_all_from_directory(p: &str, lua: &Lua) -> Result<Vec<Self>> {
+    let path = Path::new(p);
+    let mut comparisons = Vec::new();
+    if path.is_dir() {
+      for file in read_dir(path)? {
+        let entry = file?.path();
+        if entry.is_file() {
+          match read_to_string(&entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Self::from_str(s, lua))
+          {
+            Ok(comparison) => comparisons.push(comparison),
+            Err(err) => error!("skipping lua comparison {}: {}", entry.display(), err),
+          }
+        }
+      }
+    }
+    Ok(comparisons)
+  }
+
+  pub fn is_enabled(&self, run: &Run) -> bool {
+    run.custom_comparisons().iter().any(|c| *c == self.name)
+  }
+
+  /// adds the comparison to the run if it's missing, removes it otherwise
+  pub fn toggle(&self, lua: &Lua, run: &mut Run) -> Result<()> {
+    if self.is_enabled(run) {
+      run.custom_comparisons_mut().retain(|c| *c != self.name);
+      Ok(())
+    } else {
+      run
+        .add_custom_comparison(self.name.clone())
+        .map_err(|err| {
+          anyhow::Error::msg(format!("couldn't add comparison {}: {}", self.name, err))
+        })?;
+      self.apply(lua, run)
+    }
+  }
+
+  /// calls the lua generator and writes its split times in the run
+  pub fn apply(&self, lua: &Lua, run: &mut Run) -> Result<()> {
+    let segments_table = lua.create_table()?;
+    for segment in run.segments() {
+      let segment_table = lua.create_table()?;
+      segment_table.set("name", segment.name())?;
+
+      let best_segment_table = lua.create_table()?;
+      best_segment_table.set(
+        "real_time",
+        segment
+          .best_segment_time()
+          .real_time
+          .map(|t| t.total_seconds()),
+      )?;
+      best_segment_table.set(
+        "game_time",
+        segment
+          .best_segment_time()
+          .game_time
+          .map(|t| t.total_seconds()),
+      )?;
+      segment_table.set("best_segment", best_segment_table)?;
+
+      let history_table = lua.create_table()?;
+      for (index, time) in segment.segment_history() {
+        let entry_table = lua.create_table()?;
+        entry_table.set("attempt", *index)?;
+        entry_table.set("real_time", time.real_time.map(|t| t.total_seconds()))?;
+        entry_table.set("game_time", time.game_time.map(|t| t.total_seconds()))?;
+        history_table.push(entry_table)?;
+      }
+      segment_table.set("history", history_table)?;
+
+      segments_table.push(segment_table)?;
+    }
+
+    let split_times = run_with_budget(lua, || self.generate.call::<LuaTable>(segments_table))?;
+
+    for (i, segment) in run.segments_mut().iter_mut().enumerate() {
+      let split_time = match split_times.get::<Option<LuaTable>>(i + 1)? {
+        Some(t) => Time::new()
+          .with_real_time(
+            t.get::<Option<f64>>("real_time")?
+              .map(TimeSpan::from_seconds),
+          )
+          .with_game_time(
+            t.get::<Option<f64>>("game_time")?
+              .map(TimeSpan::from_seconds),
+          ),
+        None => Time::default(),
+      };
+      *segment.comparison_mut(&self.name) = split_time;
+    }
+
+    Ok(())
+  }
+}
+
+/// matches every segment of the run with a segment of the ghost run of the same name
+///
+/// names are compared case-insensitively, and every ghost segment is used at most once
+pub fn match_segments_by_name(run: &Run, ghost: &Run) -> Vec<Option<usize>> {
+  let mut used = vec![false; ghost.len()];
+  run
+    .segments()
+    .iter()
+    .map(|segment| {
+      let name = segment.name().trim().to_lowercase();
+      let index = ghost
+        .segments()
+        .iter()
+        .enumerate()
+        .position(|(i, g)| !used[i] && g.name().trim().to_lowercase() == name)?;
+      used[index] = true;
+      Some(index)
+    })
+    .collect()
+}
+
+/// adds the personal best of another run as a custom comparison
+///
+/// `mapping` gives, for every segment of the run, the index of the ghost segment to take the split time from;
+/// unmapped segments are left empty in the comparison
+pub fn import_ghost(
+  run: &mut Run,
+  ghost: &Run,
+  name: &str,
+  mapping: &[Option<usize>],
+) -> Result<()> {
+  let name = name.trim();
+  if name.is_empty() {
+    return Err(anyhow::Error::msg("comparison name can't be empty"));
+  }
+  if mapping.len() != run.len() {
+    return Err(anyhow::Error::msg(
+      "segment mapping doesn't match the run's segments",
+    ));
+  }
+
+  // refuses names of existing comparisons, and names livesplit-core reserves for races
+  run
+    .add_custom_comparison(name)
+    .map_err(|err| anyhow::Error::msg(format!("couldn't add comparison {}: {}", name, err)))?;
+  for (segment, index) in run.segments_mut().iter_mut().zip(mapping) {
+    *segment.comparison_mut(name) = index
+      .and_then(|i| ghost.segments().get(i))
+      .map(|g| g.personal_best_split_time())
+      .unwrap_or_default();
+  }
+
+  Ok(())
+}
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, path::Path};
 
 use anyhow::Result;
 use include_dir::Dir;
@@ -6,17 +6,33 @@ use log::info;
 
 /// function used to copy default components in the data dir
 ///
-/// ignores if the yast dir exists
+/// only copies the missing files, so defaults added by an update reach existing installs
+/// without overwriting the user's edits
 pub fn copy_default_components(default_dir: &Dir<'static>) -> Result<()> {
   let data_dir = dirs::data_dir().expect("couldn't get data directory");
   let mut yast_dir = data_dir.clone();
   yast_dir.push("yast/");
 
-  if !yast_dir.try_exists()? {
-    info!("couldn't find defaults in data directory; copying");
-    fs::create_dir(yast_dir.clone())?;
-    default_dir.extract(yast_dir)?;
-  }
+  copy_missing(default_dir, &yast_dir)
+}
 
+/// copies the files of `dir` missing from `target`, recursively
+fn copy_missing(dir: &Dir<'static>, target: &Path) -> Result<()> {
+  for file in dir.files() {
+    let path = target.join(file.path());
+    if !path.try_exists()? {
+      info!(
+        "couldn't find default {} in data directory; copying",
+        file.path().display()
+      );
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::write(path, file.contents())?;
+    }
+  }
+  for sub_dir in dir.dirs() {
+    copy_missing(sub_dir, target)?;
+  }
   Ok(())
 }
@@ -0,0 +1,152 @@
+//! statistics export to CSV and JSON
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use livesplit_core::{Run, TimingMethod};
+use serde::Serialize;
+
+use crate::statistics::{AttemptSummary, SegmentStatistics, SumOfBestEntry, sum_of_best_history};
+
+/// single entry of a segment's history
+#[derive(Serialize)]
+pub struct SegmentHistoryEntry {
+  pub segment: usize,
+  pub attempt: i32,
+  pub real_time: Option<f64>,
+  pub game_time: Option<f64>,
+}
+
+/// everything exported from a run
+#[derive(Serialize)]
+pub struct StatisticsExport {
+  pub game_name: String,
+  pub category_name: String,
+  pub timing_method: String,
+  pub attempts: Vec<AttemptSummary>,
+  pub segment_history: Vec<SegmentHistoryEntry>,
+  pub segments: Vec<SegmentStatistics>,
+  pub sum_of_best_history: Vec<SumOfBestEntry>,
+}
+
+impl StatisticsExport {
+  pub fn from_run(run: &Run, timing_method: TimingMethod) -> Self {
+    let mut segment_history = Vec::new();
+    for (i, segment) in run.segments().iter().enumerate() {
+      for (attempt, time) in segment.segment_history() {
+        segment_history.push(SegmentHistoryEntry {
+          segment: i,
+          attempt: *attempt,
+          real_time: time.real_time.map(|t| t.total_seconds()),
+          game_time: time.game_time.map(|t| t.total_seconds()),
+        });
+      }
+    }
+
+    Self {
+      game_name: run.game_name().to_string(),
+      category_name: run.category_name().to_string(),
+      timing_method: format!("{:?}", timing_method),
+      attempts: AttemptSummary::from_run(run, timing_method),
+      segment_history,
+      segments: SegmentStatistics::from_run(run, timing_method),
+      sum_of_best_history: sum_of_best_history(run, timing_method),
+    }
+  }
+}
+
+/// exports all statistics of a run in a single JSON file
+pub fn export_statistics_json(run: &Run, timing_method: TimingMethod, path: &Path) -> Result<()> {
+  let export = StatisticsExport::from_run(run, timing_method);
+  fs::write(path, serde_json::to_string_pretty(&export)?)?;
+  Ok(())
+}
+
+/// exports all statistics of a run as CSV files in the given folder
+///
+/// as CSV can only hold a single table, this writes `attempts.csv`, `segment_history.csv`,
+/// `segments.csv` and `sum_of_best.csv` in the folder
+pub fn export_statistics_csv(run: &Run, timing_method: TimingMethod, folder: &Path) -> Result<()> {
+  let export = StatisticsExport::from_run(run, timing_method);
+
+  let file = |name: &str| folder.join(format!("{}.csv", name));
+
+  let mut attempts = String::from("index,started,duration,time,reset_segment,is_personal_best\n");
+  for a in &export.attempts {
+    attempts.push_str(&csv_row(&[
+      a.index.to_string(),
+      a.started.clone().unwrap_or_default(),
+      csv_number(a.duration),
+      csv_number(a.time),
+      a.reset_segment.map(|s| s.to_string()).unwrap_or_default(),
+      a.is_personal_best.to_string(),
+    ]));
+  }
+  fs::write(file("attempts"), attempts)?;
+
+  let mut segment_history = String::from("segment,segment_name,attempt,real_time,game_time\n");
+  for e in &export.segment_history {
+    segment_history.push_str(&csv_row(&[
+      e.segment.to_string(),
+      run
+        .segments()
+        .get(e.segment)
+        .map(|s| s.name().to_string())
+        .unwrap_or_default(),
+      e.attempt.to_string(),
+      csv_number(e.real_time),
+      csv_number(e.game_time),
+    ]));
+  }
+  fs::write(file("segment_history"), segment_history)?;
+
+  let mut segments =
+    String::from("segment,name,count,golds,resets,average,median,best,worst,standard_deviation\n");
+  for (i, s) in export.segments.iter().enumerate() {
+    segments.push_str(&csv_row(&[
+      i.to_string(),
+      s.name.clone(),
+      s.count.to_string(),
+      s.golds.to_string(),
+      s.resets.to_string(),
+      csv_number(s.average),
+      csv_number(s.median),
+      csv_number(s.best),
+      csv_number(s.worst),
+      csv_number(s.standard_deviation),
+    ]));
+  }
+  fs::write(file("segments"), segments)?;
+
+  let mut sum_of_best = String::from("attempt,sum_of_best\n");
+  for e in &export.sum_of_best_history {
+    sum_of_best.push_str(&csv_row(&[
+      e.attempt.to_string(),
+      csv_number(e.sum_of_best),
+    ]));
+  }
+  fs::write(file("sum_of_best"), sum_of_best)?;
+
+  Ok(())
+}
+
+fn csv_number(n: Option<f64>) -> String {
+  n.map(|n| n.to_string()).unwrap_or_default()
+}
+
+/// joins fields into a CSV line, quoting them when needed
+fn csv_row(fields: &[String]) -> String {
+  let mut row = fields
+    .iter()
+    .map(|f| {
+      if f.contains([',', '"', '\n']) {
+        format!("\"{}\"", f.replace('"', "\"\""))
+      } else {
+        f.clone()
+      }
+    })
+    .collect::<Vec<String>>()
+    .join(",");
+  row.push('\n');
+  row
+}
@@ -7,45 +7,120 @@ use std::{
 };
 
 use anyhow::Result;
-use iced::Element;
+use iced::{
+  Border, Color, Element,
+  widget::{column, container, text},
+};
+use log::error;
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-  layout::settings::LayoutSettings,
+  layout::{
+    memo::{Memo, Memos, Tracker, track_globals, untracked_globals},
+    settings::LayoutSettings,
+    state::state_table,
+  },
   lua::{
+    budget::{MAX_STRIKES, is_budget_exceeded, is_disabled, run_with_budget, strike},
+    sandbox::{component_environment, component_folder, grant_capabilities},
     settings::{SettingsFactory, SettingsFactoryEntryContent},
     widgets::LuaWidget,
   },
   repository::Repository,
 };
 
+/// color of the placeholders shown instead of failing components
+const ERROR_COLOR: Color = Color::from_rgb(1., 0.3, 0.3);
+
+/// last build error of every failing component, by path, so errors are only logged when they change
+#[derive(Default)]
+struct ComponentErrors(HashMap<Vec<usize>, String>);
+
+/// placeholder shown instead of a component that couldn't be built
+fn error_placeholder<'a, M: 'a>(name: &str, path: &Vec<usize>, message: String) -> Element<'a, M> {
+  container(
+    column(vec![
+      text(format!("{} {:?}", name, path))
+        .size(12.)
+        .color(ERROR_COLOR)
+        .into(),
+      text(message).size(10.).color(ERROR_COLOR).into(),
+    ])
+    .spacing(2.),
+  )
+  .padding(2.)
+  .style(|_| container::Style {
+    border: Border {
+      color: ERROR_COLOR,
+      width: 1.,
+      ..Default::default()
+    },
+    ..Default::default()
+  })
+  .into()
+}
+
 /// serializable component
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Component {
   pub name: String,
   pub author: String,
   pub children: Vec<Component>,
+  /// index of the timer the component reads its values from
+  #[serde(default)]
+  pub timer: usize,
 
   /// this is not serialized. it is initialized by the layout loader
   #[serde(skip)]
   widget: Option<LuaFunction>,
+  /// this is not serialized. whether the widget is reused while the values it read are unchanged
+  #[serde(skip)]
+  memoize: bool,
+  /// this is not serialized. capabilities declared by the component, see [`CAPABILITIES`]
+  ///
+  /// [`CAPABILITIES`]: crate::lua::sandbox::CAPABILITIES
+  #[serde(skip)]
+  pub capabilities: Vec<String>,
+  /// this is not serialized. keys of the component's `state` table saved to disk
+  #[serde(skip)]
+  persist: Vec<String>,
   /// this is not serialized. this is the settings factory
   #[serde(skip)]
   pub parameters: SettingsFactory,
 }
 
 impl Component {
+  /// loads a component from its source, in a new environment of its own
+  ///
+  /// the globals of the declared capabilities are given once the source is evaluated,
+  /// so they're only available in the component's functions
   pub fn from_str(s: String, lua: &Lua) -> Result<Self> {
-    let t = lua.load(s).eval::<LuaTable>()?;
+    let env = component_environment(lua)?;
+    let t = run_with_budget(lua, || {
+      lua.load(s).set_environment(env.clone()).eval::<LuaTable>()
+    })?;
+
+    let name = t.get::<String>("name")?;
+    let capabilities = t
+      .get::<Option<Vec<String>>>("capabilities")?
+      .unwrap_or_default();
+    grant_capabilities(lua, &env, &name, component_folder(&name)?, &capabilities)?;
 
     let r = Self {
-      name: t.get("name")?,
+      name,
       author: t.get("author")?,
       widget: Some(t.get("widget")?),
+      // reads of the current time can't be tracked, so components reading it aren't memoized by default
+      memoize: t
+        .get::<Option<bool>>("memoize")?
+        .unwrap_or(!capabilities.iter().any(|c| c == "time")),
+      capabilities,
+      persist: t.get::<Option<Vec<String>>>("persist")?.unwrap_or_default(),
 
-      parameters: t.call_function("settings", ())?,
+      parameters: run_with_budget(lua, || t.call_function("settings", ()))?,
       children: Vec::new(),
+      timer: 0,
     };
 
     Ok(r)
@@ -68,6 +143,9 @@ impl Component {
     )?;
 
     self.widget = template.widget;
+    self.memoize = template.memoize;
+    self.capabilities = template.capabilities;
+    self.persist = template.persist;
     self.parameters = template.parameters;
 
     let comp_parameters = layout_settings.get_mut(&path).ok_or(anyhow::Error::msg(
@@ -96,6 +174,18 @@ impl Component {
     Ok(())
   }
 
+  /// updates the timer indices of the component and its children after a timer was removed
+  pub fn remove_timer(&mut self, timer: usize) {
+    if self.timer == timer {
+      self.timer = 0;
+    } else if self.timer > timer {
+      self.timer -= 1;
+    }
+    for child in &mut self.children {
+      child.remove_timer(timer);
+    }
+  }
+
   pub fn get_from_path(&self, path: Vec<usize>) -> Result<&Self> {
     if path.len() == 0 {
       Ok(self)
@@ -122,6 +212,9 @@ impl Component {
     }
   }
 
+  /// reads the sources of every component of a directory, by component name
+  ///
+  /// components failing to load, or exceeding their time budget while loading, are skipped
   pub fn import_all_from_directory(p: &str, lua: &Lua) -> Result<HashMap<String, String>> {
     let path = Path::new(p);
     let mut components = HashMap::new();
@@ -129,64 +222,191 @@ impl Component {
       for file in read_dir(path)? {
         let entry = file?.path();
         if entry.is_file() {
-          let st = read_to_string(entry)?;
-          let name = lua
-            .load(st.clone())
-            .eval::<LuaTable>()?
-            .get::<String>("name")?;
-          components.insert(name, st);
+          match Self::read_source(&entry, lua) {
+            Ok((name, st)) => {
+              components.insert(name, st);
+            }
+            Err(err) => error!("skipping component {}: {}", entry.display(), err),
+          }
         }
       }
     }
     Ok(components)
   }
 
+  /// source of a component file, with the name of the component
+  fn read_source(path: &Path, lua: &Lua) -> Result<(String, String)> {
+    let st = read_to_string(path)?;
+    let env = component_environment(lua)?;
+    let name = run_with_budget(lua, || {
+      lua
+        .load(st.as_str())
+        .set_environment(env)
+        .eval::<LuaTable>()?
+        .get::<String>("name")
+    })?;
+    Ok((name, st))
+  }
+
+  /// widget memoized by the previous build, if nothing it was built from changed
+  fn memoized(
+    &self,
+    lua: &Lua,
+    widget: &LuaFunction,
+    path: &Vec<usize>,
+    layout_settings: &LayoutSettings,
+    repository: &Repository,
+  ) -> Result<Option<LuaWidget>> {
+    let Some(memos) = lua.app_data_ref::<Memos>() else {
+      return Ok(None);
+    };
+    let Some(memo) = memos.0.get(path) else {
+      return Ok(None);
+    };
+    if memo.function != *widget || memo.timer != self.timer || memo.children != self.children.len()
+    {
+      return Ok(None);
+    }
+    Ok(
+      memo
+        .is_valid(lua, path, layout_settings, repository)?
+        .then(|| memo.widget.clone()),
+    )
+  }
+
+  /// builds the component's widget
+  ///
+  /// if the component fails to build, a placeholder showing the error is built instead,
+  /// so the rest of the layout stays usable
   pub fn build<'a, M: 'a>(
     &self,
     lua: &Lua,
     path: Vec<usize>,
     layout_settings: &LayoutSettings,
     repository: &Repository,
+  ) -> Element<'a, M> {
+    match self.try_build(lua, path.clone(), layout_settings, repository) {
+      Ok(element) => {
+        if let Some(mut errors) = lua.app_data_mut::<ComponentErrors>() {
+          errors.0.remove(&path);
+        }
+        element
+      }
+      Err(err) => {
+        let message = err.to_string();
+        if lua.app_data_ref::<ComponentErrors>().is_none() {
+          lua.set_app_data(ComponentErrors::default());
+        }
+        if let Some(mut errors) = lua.app_data_mut::<ComponentErrors>() {
+          if errors.0.get(&path) != Some(&message) {
+            error!(
+              "couldn't build component {} {:?}: {}",
+              self.name, path, message
+            );
+            errors.0.insert(path.clone(), message.clone());
+          }
+        }
+        error_placeholder(&self.name, &path, message)
+      }
+    }
+  }
+
+  /// builds the component's widget, failing if the component or its own widgets fail
+  ///
+  /// the `setting` function given to the component borrows the layout settings and the repository,
+  /// so it only lives for the duration of the build
+  ///
+  /// unless the component sets `memoize = false`, the values read by its `widget` function are recorded,
+  /// and the function isn't called again until one of them changes. children are memoized separately.
+  /// its `state` table isn't tracked, so components updating it on every build shouldn't be memoized
+  fn try_build<'a, M: 'a>(
+    &self,
+    lua: &Lua,
+    path: Vec<usize>,
+    layout_settings: &LayoutSettings,
+    repository: &Repository,
   ) -> Result<Element<'a, M>> {
     if let Some(widget) = &self.widget {
+      if is_disabled(lua, &path) {
+        return Err(anyhow::Error::msg(format!(
+          "disabled after exceeding its time budget {} times, reload the layout to enable it again",
+          MAX_STRIKES
+        )));
+      }
+
+      if self.memoize {
+        if let Some(memoized) = self.memoized(lua, widget, &path, layout_settings, repository)? {
+          return memoized.build(&self, lua, path, layout_settings, repository);
+        }
+      }
+
       let env = widget.environment().ok_or(anyhow::Error::msg(
         "couldn't get environment when building component",
       ))?;
 
-      let pc = path.clone();
-      let rc = repository.clone();
-      let lsc = layout_settings.clone();
-      let lc = lua.clone();
-      let setting = lua.create_function(move |_, name: String| {
-        let lsc = lsc.clone();
-
-        if let Some(a) = lsc.get(&pc.clone()) {
-          if let Some(b) = a.get(&name) {
-            Ok(b.inner(&lc, &rc, pc.clone(), name.clone()))
-          } else {
-            Err(LuaError::external(anyhow::Error::msg(
-              "can't find setting in layout settings",
-            )))
-          }
-        } else {
-          Err(LuaError::external(anyhow::Error::msg(format!(
-            "can't find component {:?} in layout settings",
-            pc.clone()
-          ))))
-        }
-      })?;
-      env.set("setting", setting)?;
+      let tracker = Tracker::default();
+      if self.memoize {
+        track_globals(lua, &env, &tracker, self.timer)?;
+      } else {
+        untracked_globals(lua, &env, self.timer)?;
+      }
 
-      let children = env.get::<LuaTable>("children")?;
-      children.set("len", self.children.len())?;
-      env.set("children", children)?;
+      env.raw_set("state", state_table(lua, &path, widget, &self.persist)?)?;
+      env
+        .get::<LuaTable>("children")?
+        .set("len", self.children.len())?;
 
-      widget.set_environment(env)?;
+      let component_settings = layout_settings.get(&path);
+
+      let lua_widget = lua
+        .scope(|scope| {
+          let setting = scope.create_function(|lua, name: String| {
+            let component_settings = component_settings.ok_or_else(|| {
+              LuaError::external(anyhow::Error::msg(format!(
+                "can't find component {:?} in layout settings",
+                path
+              )))
+            })?;
+            let value = component_settings.get(&name).ok_or_else(|| {
+              LuaError::external(anyhow::Error::msg("can't find setting in layout settings"))
+            })?;
+            tracker.setting(layout_settings, repository, &path, &name);
+            value
+              .inner(lua, repository, path.clone(), name)
+              .map_err(LuaError::external)
+          })?;
+          env.set("setting", setting)?;
+
+          run_with_budget(lua, || widget.call::<LuaWidget>(()))
+        })
+        .map_err(|err| {
+          if is_budget_exceeded(&err) {
+            strike(lua, &path);
+          }
+          err
+        })?;
+
+      if self.memoize {
+        if lua.app_data_ref::<Memos>().is_none() {
+          lua.set_app_data(Memos::default());
+        }
+        lua
+          .app_data_mut::<Memos>()
+          .ok_or(anyhow::Error::msg("couldn't get component memos"))?
+          .0
+          .insert(
+            path.clone(),
+            Memo::new(
+              widget.clone(),
+              self.timer,
+              self.children.len(),
+              tracker,
+              lua_widget.clone(),
+            ),
+          );
+      }
 
-      let e = widget
-        .call::<LuaWidget>(())?
-        .build(&self, lua, path, layout_settings, repository)?;
-      Ok(e)
+      lua_widget.build(&self, lua, path, layout_settings, repository)
     } else {
       Err(anyhow::Error::msg(
         "no widget lua function in component (could be a loading error!)",
@@ -0,0 +1,382 @@
+//! memoization of component widgets
+//!
+//! while a component is built, the values it reads from the injected tables and its settings are recorded.
+//! on the next build, the previous widget is reused if none of them changed
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use iced::advanced::image;
+use mlua::prelude::*;
+
+use crate::{
+  layout::settings::{LayoutSettings, SettingsValue},
+  lua::{
+    sandbox::{read_only, read_only_error, register_proxy},
+    widgets::LuaWidget,
+  },
+  repository::Repository,
+};
+
+/// globals holding injected values, which are tracked when read by components
+const TRACKED_GLOBALS: [&str; 5] = ["timers", "practice", "marathon", "reset_reasons", "frame"];
+
+/// table a tracked value is read from
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Root {
+  /// `snapshot`, `run` or `analysis` of the component's timer
+  Timer(&'static str),
+  Global(&'static str),
+}
+
+/// setting value as seen by a component, images being compared by handle
+#[derive(Debug, PartialEq)]
+enum SettingFingerprint {
+  Value(SettingsValue),
+  Image(Option<image::Id>),
+  Missing,
+}
+
+impl SettingFingerprint {
+  fn new(
+    layout_settings: &LayoutSettings,
+    repository: &Repository,
+    path: &Vec<usize>,
+    name: &str,
+  ) -> Self {
+    match layout_settings.get(path).and_then(|s| s.get(name)) {
+      Some(SettingsValue::Image(_)) => Self::Image(
+        repository
+          .layout_images
+          .get(&(path.clone(), name.to_string()))
+          .and_then(|h| h.as_ref().map(|h| h.id())),
+      ),
+      Some(value) => Self::Value(value.clone()),
+      None => Self::Missing,
+    }
+  }
+}
+
+/// value read by a component
+#[derive(Debug)]
+enum Dependency {
+  /// whether a root is present
+  Present(Root, bool),
+  Value(Root, Vec<LuaValue>, LuaValue),
+  Length(Root, Vec<LuaValue>, usize),
+  Keys(Root, Vec<LuaValue>, Vec<LuaValue>),
+  Setting(String, SettingFingerprint),
+}
+
+/// dependencies recorded during a build
+#[derive(Clone, Default)]
+pub(crate) struct Tracker(Rc<RefCell<Vec<Dependency>>>);
+
+impl Tracker {
+  fn push(&self, dependency: Dependency) {
+    self.0.borrow_mut().push(dependency);
+  }
+
+  /// records a setting read, once per setting
+  pub(crate) fn setting(
+    &self,
+    layout_settings: &LayoutSettings,
+    repository: &Repository,
+    path: &Vec<usize>,
+    name: &str,
+  ) {
+    let known = self
+      .0
+      .borrow()
+      .iter()
+      .any(|d| matches!(d, Dependency::Setting(n, _) if n == name));
+    if !known {
+      self.push(Dependency::Setting(
+        name.to_string(),
+        SettingFingerprint::new(layout_settings, repository, path, name),
+      ));
+    }
+  }
+
+  /// wraps a table in a read-only proxy recording every value read through it
+  fn proxy(
+    &self,
+    lua: &Lua,
+    root: Root,
+    path: Vec<LuaValue>,
+    target: LuaTable,
+  ) -> LuaResult<LuaTable> {
+    let proxy = lua.create_table()?;
+    let metatable = lua.create_table()?;
+
+    // values already read, which don't need to be recorded again
+    let read = lua.create_table()?;
+
+    let tracker = self.clone();
+    let index_root = root.clone();
+    let index_path = path.clone();
+    let index_target = target.clone();
+    metatable.raw_set(
+      "__index",
+      lua.create_function(move |lua, (_, key): (LuaValue, LuaValue)| {
+        let value = read.raw_get::<LuaValue>(key.clone())?;
+        if !value.is_nil() {
+          return Ok(value);
+        }
+        let mut path = index_path.clone();
+        path.push(key.clone());
+        let value = match index_target.raw_get::<LuaValue>(key.clone())? {
+          LuaValue::Table(t) => LuaValue::Table(tracker.proxy(lua, index_root.clone(), path, t)?),
+          value => {
+            tracker.push(Dependency::Value(index_root.clone(), path, value.clone()));
+            value
+          }
+        };
+        read.raw_set(key, value.clone())?;
+        Ok(value)
+      })?,
+    )?;
+    metatable.raw_set("__newindex", read_only_error(lua)?)?;
+
+    let tracker = self.clone();
+    let length_root = root.clone();
+    let length_path = path.clone();
+    let length_target = target.clone();
+    metatable.raw_set(
+      "__len",
+      lua.create_function(move |_, _: LuaValue| {
+        let length = length_target.raw_len();
+        tracker.push(Dependency::Length(
+          length_root.clone(),
+          length_path.clone(),
+          length,
+        ));
+        Ok(length)
+      })?,
+    )?;
+
+    let tracker = self.clone();
+    let pairs_root = root.clone();
+    let pairs_path = path.clone();
+    let pairs_target = target.clone();
+    metatable.raw_set(
+      "__pairs",
+      lua.create_function(move |lua, proxy: LuaTable| {
+        let keys = table_keys(&pairs_target)?;
+        tracker.push(Dependency::Keys(
+          pairs_root.clone(),
+          pairs_path.clone(),
+          keys.clone(),
+        ));
+
+        let mut next = 0;
+        lua.create_function_mut(move |_, _: LuaMultiValue| match keys.get(next) {
+          Some(key) => {
+            next += 1;
+            Ok((key.clone(), proxy.get::<LuaValue>(key.clone())?))
+          }
+          None => Ok((LuaNil, LuaNil)),
+        })
+      })?,
+    )?;
+
+    // iterations with `next` record the keys when they start, like `pairs`
+    let tracker = self.clone();
+    let next = lua.globals().raw_get::<LuaFunction>("next")?;
+    register_proxy(
+      lua,
+      &proxy,
+      lua.create_function(move |_, (proxy, key): (LuaTable, LuaValue)| {
+        if key.is_nil() {
+          tracker.push(Dependency::Keys(
+            root.clone(),
+            path.clone(),
+            table_keys(&target)?,
+          ));
+        }
+        let (key, _) = next.call::<(LuaValue, LuaValue)>((target.clone(), key))?;
+        if key.is_nil() {
+          return Ok((LuaNil, LuaNil));
+        }
+        let value = proxy.get::<LuaValue>(key.clone())?;
+        Ok((key, value))
+      })?,
+    )?;
+
+    metatable.raw_set("__metatable", false)?;
+    proxy.set_metatable(Some(metatable))?;
+    Ok(proxy)
+  }
+}
+
+/// keys of a table, in iteration order
+fn table_keys(table: &LuaTable) -> LuaResult<Vec<LuaValue>> {
+  table
+    .pairs::<LuaValue, LuaValue>()
+    .map(|p| p.map(|(k, _)| k))
+    .collect()
+}
+
+/// current value of a root, for the given timer
+fn root_value(lua: &Lua, root: &Root, timer: usize) -> LuaResult<LuaValue> {
+  match root {
+    Root::Global(name) => lua.globals().raw_get(*name),
+    Root::Timer(name) => {
+      let LuaValue::Table(timers) = lua.globals().raw_get("timers")? else {
+        return Ok(LuaNil);
+      };
+      let timer_values = match timers.raw_get::<Option<LuaTable>>(timer + 1)? {
+        Some(t) => t,
+        None => match timers.raw_get::<Option<LuaTable>>(1)? {
+          Some(t) => t,
+          None => return Ok(LuaNil),
+        },
+      };
+      timer_values.raw_get(*name)
+    }
+  }
+}
+
+/// current value at a path, `None` if the path goes through something that isn't a table
+fn resolve(lua: &Lua, root: &Root, path: &[LuaValue], timer: usize) -> LuaResult<Option<LuaValue>> {
+  let mut value = root_value(lua, root, timer)?;
+  for key in path {
+    value = match value {
+      LuaValue::Table(t) => t.raw_get(key.clone())?,
+      _ => return Ok(None),
+    };
+  }
+  Ok(Some(value))
+}
+
+fn resolve_table(
+  lua: &Lua,
+  root: &Root,
+  path: &[LuaValue],
+  timer: usize,
+) -> LuaResult<Option<LuaTable>> {
+  Ok(match resolve(lua, root, path, timer)? {
+    Some(LuaValue::Table(t)) => Some(t),
+    _ => None,
+  })
+}
+
+/// sets the globals read by components, wrapped in proxies recording what is read
+pub(crate) fn track_globals(
+  lua: &Lua,
+  env: &LuaTable,
+  tracker: &Tracker,
+  timer: usize,
+) -> LuaResult<()> {
+  for name in ["snapshot", "run", "analysis"] {
+    let root = Root::Timer(name);
+    let value = match root_value(lua, &root, timer)? {
+      LuaValue::Table(t) => LuaValue::Table(tracker.proxy(lua, root, vec![], t)?),
+      value => value,
+    };
+    env.set(name, value)?;
+  }
+
+  for name in TRACKED_GLOBALS {
+    let root = Root::Global(name);
+    let value = match root_value(lua, &root, timer)? {
+      LuaValue::Table(t) => {
+        tracker.push(Dependency::Present(root.clone(), true));
+        LuaValue::Table(tracker.proxy(lua, root, vec![], t)?)
+      }
+      value => {
+        tracker.push(Dependency::Present(root, false));
+        value
+      }
+    };
+    env.set(name, value)?;
+  }
+
+  Ok(())
+}
+
+/// sets the globals read by components, wrapped in read-only proxies without tracking
+pub(crate) fn untracked_globals(lua: &Lua, env: &LuaTable, timer: usize) -> LuaResult<()> {
+  let read_only_value = |root: &Root| -> LuaResult<LuaValue> {
+    Ok(match root_value(lua, root, timer)? {
+      LuaValue::Table(t) => LuaValue::Table(read_only(lua, t)?),
+      value => value,
+    })
+  };
+  for name in ["snapshot", "run", "analysis"] {
+    env.set(name, read_only_value(&Root::Timer(name))?)?;
+  }
+  for name in TRACKED_GLOBALS {
+    env.set(name, read_only_value(&Root::Global(name))?)?;
+  }
+  Ok(())
+}
+
+/// widget built by a component, with everything it was built from
+pub(crate) struct Memo {
+  pub function: LuaFunction,
+  pub timer: usize,
+  pub children: usize,
+  dependencies: Vec<Dependency>,
+  pub widget: LuaWidget,
+}
+
+impl Memo {
+  pub(crate) fn new(
+    function: LuaFunction,
+    timer: usize,
+    children: usize,
+    tracker: Tracker,
+    widget: LuaWidget,
+  ) -> Self {
+    Self {
+      function,
+      timer,
+      children,
+      dependencies: tracker.0.take(),
+      widget,
+    }
+  }
+
+  /// whether every value read during the build is unchanged
+  pub(crate) fn is_valid(
+    &self,
+    lua: &Lua,
+    path: &Vec<usize>,
+    layout_settings: &LayoutSettings,
+    repository: &Repository,
+  ) -> LuaResult<bool> {
+    for dependency in &self.dependencies {
+      let valid = match dependency {
+        Dependency::Present(root, present) => {
+          root_value(lua, root, self.timer)?.is_nil() != *present
+        }
+        Dependency::Value(root, p, value) => {
+          resolve(lua, root, p, self.timer)?.as_ref() == Some(value)
+        }
+        Dependency::Length(root, p, length) => resolve_table(lua, root, p, self.timer)?
+          .map(|t| t.raw_len() == *length)
+          .unwrap_or(false),
+        Dependency::Keys(root, p, keys) => match resolve_table(lua, root, p, self.timer)? {
+          Some(t) => table_keys(&t)? == *keys,
+          None => false,
+        },
+        Dependency::Setting(name, fingerprint) => {
+          SettingFingerprint::new(layout_settings, repository, path, name) == *fingerprint
+        }
+      };
+      if !valid {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+}
+
+/// memoized widgets of every component, by path
+#[derive(Default)]
+pub(crate) struct Memos(pub HashMap<Vec<usize>, Memo>);
+
+/// forgets every memoized widget, forcing components to run their `widget` function again
+pub fn invalidate_memos(lua: &Lua) {
+  lua.remove_app_data::<Memos>();
+}
@@ -3,9 +3,10 @@
 use anyhow::Result;
 use handy_keys::Hotkey;
 use iced::advanced::image;
+use livesplit_core::{Timer, TimerPhase};
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs};
+use std::{collections::HashMap, fs, path::Path};
 use strum::EnumIter;
 
 use crate::{
@@ -13,12 +14,24 @@ use crate::{
     component::Component,
     settings::{LayoutSettings, SettingsValue},
   },
+  lua::budget::reset_strikes,
   repository::Repository,
 };
 
 pub mod component;
+pub mod memo;
 pub mod moving;
 pub mod settings;
+pub mod state;
+
+/// redraw rate while a timer is running, for layouts that don't set one
+pub const DEFAULT_MAX_REFRESH_RATE: f64 = 60.;
+/// redraw rate while no timer is running, hotkeys and input waking YAST up on their own
+pub const IDLE_REFRESH_RATE: f64 = 1.;
+
+fn default_max_refresh_rate() -> f64 {
+  DEFAULT_MAX_REFRESH_RATE
+}
 
 /// main serializable layout structure
 #[derive(Serialize, Deserialize)]
@@ -29,8 +42,14 @@ pub struct Layout {
 
   pub settings: LayoutSettings,
   pub hotkeys: HashMap<HotkeyAction, Hotkey>,
+  /// hotkeys of the additional timers, the first entry being the second timer's
+  #[serde(default)]
+  pub extra_hotkeys: Vec<HashMap<HotkeyAction, Hotkey>>,
   pub width: f32,
   pub height: f32,
+  /// redraw rate while a timer is running, in Hz
+  #[serde(default = "default_max_refresh_rate")]
+  pub max_refresh_rate: f64,
 }
 
 impl Layout {
@@ -39,8 +58,12 @@ impl Layout {
     components: &HashMap<String, String>,
     lua: &Lua,
     content: String,
+    path: Option<&Path>,
   ) -> Result<Self> {
     let mut layout = ron::from_str::<Self>(&content)?;
+    memo::invalidate_memos(lua);
+    reset_strikes(lua);
+    state::reset_states(lua, path);
 
     for (comp_path, comp_parameters) in &layout.settings {
       for (param_name, param_value) in comp_parameters {
@@ -63,6 +86,55 @@ impl Layout {
     Ok(layout)
   }
 
+  /// redraw rate for the given timer phases, in Hz
+  ///
+  /// the timers are only redrawn at the full rate while one of them is running, as nothing moves otherwise
+  pub fn refresh_rate(&self, phases: impl IntoIterator<Item = TimerPhase>) -> f64 {
+    if phases.into_iter().any(|p| p == TimerPhase::Running) {
+      // layouts edited by hand could hold a rate the update interval can't be computed from
+      if self.max_refresh_rate.is_finite() {
+        self.max_refresh_rate.max(IDLE_REFRESH_RATE)
+      } else {
+        DEFAULT_MAX_REFRESH_RATE
+      }
+    } else {
+      IDLE_REFRESH_RATE
+    }
+  }
+
+  /// hotkeys of a timer, by timer index
+  pub fn timer_hotkeys(&self, timer: usize) -> Option<&HashMap<HotkeyAction, Hotkey>> {
+    match timer {
+      0 => Some(&self.hotkeys),
+      _ => self.extra_hotkeys.get(timer - 1),
+    }
+  }
+
+  /// hotkeys of a timer, by timer index, creating the missing hotkey sets
+  pub fn timer_hotkeys_mut(&mut self, timer: usize) -> &mut HashMap<HotkeyAction, Hotkey> {
+    match timer {
+      0 => &mut self.hotkeys,
+      _ => {
+        if self.extra_hotkeys.len() < timer {
+          self.extra_hotkeys.resize_with(timer, HashMap::new);
+        }
+        &mut self.extra_hotkeys[timer - 1]
+      }
+    }
+  }
+
+  /// forgets a removed timer, by timer index, so the hotkeys and components of the following timers keep following them
+  ///
+  /// components displaying the removed timer display the first one instead
+  pub fn remove_timer(&mut self, timer: usize) {
+    if timer > 0 && timer <= self.extra_hotkeys.len() {
+      self.extra_hotkeys.remove(timer - 1);
+    }
+    if let Some(content) = &mut self.content {
+      content.remove_timer(timer);
+    }
+  }
+
   pub fn save(&self, path: &str) -> Result<()> {
     let s = ron::to_string(self)?;
     fs::write(path, s)?;
@@ -79,8 +151,10 @@ impl Default for Layout {
 
       settings: HashMap::new(),
       hotkeys: HashMap::new(),
+      extra_hotkeys: Vec::new(),
       width: 200.,
       height: 500.,
+      max_refresh_rate: DEFAULT_MAX_REFRESH_RATE,
     }
   }
 }
@@ -93,6 +167,10 @@ pub enum HotkeyAction {
   SplitTimer,
   ResetTimerWithoutSaving,
   ResetTimer,
+  ResetWithReason1,
+  ResetWithReason2,
+  ResetWithReason3,
+  ResetWithReason4,
   SkipSplit,
   UndoSplit,
   PauseTimer,
@@ -100,3 +178,59 @@ pub enum HotkeyAction {
   NextComparison,
   PreviousComparison,
 }
+
+impl HotkeyAction {
+  /// index of the reset reason tagged by the action, if it's a `ResetWithReason` one
+  pub fn reset_reason(&self) -> Option<usize> {
+    match self {
+      HotkeyAction::ResetWithReason1 => Some(0),
+      HotkeyAction::ResetWithReason2 => Some(1),
+      HotkeyAction::ResetWithReason3 => Some(2),
+      HotkeyAction::ResetWithReason4 => Some(3),
+      _ => None,
+    }
+  }
+
+  /// applies the action to a timer
+  pub fn apply(&self, timer: &mut Timer) {
+    match self {
+      HotkeyAction::StartOrSplitTimer => {
+        timer.split_or_start();
+      }
+      HotkeyAction::StartTimer => {
+        timer.start();
+      }
+      HotkeyAction::SplitTimer => {
+        timer.split();
+      }
+      HotkeyAction::ResetTimerWithoutSaving => {
+        timer.reset(false);
+      }
+      HotkeyAction::ResetTimer
+      | HotkeyAction::ResetWithReason1
+      | HotkeyAction::ResetWithReason2
+      | HotkeyAction::ResetWithReason3
+      | HotkeyAction::ResetWithReason4 => {
+        timer.reset(true);
+      }
+      HotkeyAction::SkipSplit => {
+        timer.skip_split();
+      }
+      HotkeyAction::UndoSplit => {
+        timer.undo_split();
+      }
+      HotkeyAction::PauseTimer => {
+        timer.toggle_pause();
+      }
+      HotkeyAction::ToggleTimingMethod => {
+        timer.toggle_timing_method();
+      }
+      HotkeyAction::NextComparison => {
+        timer.switch_to_next_comparison();
+      }
+      HotkeyAction::PreviousComparison => {
+        timer.switch_to_previous_comparison();
+      }
+    }
+  }
+}
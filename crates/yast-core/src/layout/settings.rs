@@ -11,7 +11,7 @@ use crate::{lua::widgets::image::ImageHandleLua, repository::Repository};
 pub type LayoutSettings = HashMap<Vec<usize>, HashMap<String, SettingsValue>>;
 
 /// serialized setting value
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SettingsValue {
   Boolean(bool),
   String(String),
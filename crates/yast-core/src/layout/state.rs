@@ -0,0 +1,228 @@
+//! per-component state
+//!
+//! every component instance gets a `state` table surviving across builds, reset when a layout is loaded.
+//! keys listed in the component's `persist` field are saved to disk, by layout file and component path
+
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use log::error;
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::sanitize;
+
+/// maximum depth of persisted tables, so cyclic tables can't be persisted forever
+const MAX_DEPTH: usize = 16;
+
+/// value of a persisted key, functions and userdata being skipped
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum PersistedValue {
+  Boolean(bool),
+  Integer(i64),
+  Number(f64),
+  String(String),
+  Table(Vec<(PersistedValue, PersistedValue)>),
+}
+
+impl PersistedValue {
+  fn from_lua(value: &LuaValue, depth: usize) -> LuaResult<Option<Self>> {
+    Ok(match value {
+      LuaValue::Boolean(b) => Some(Self::Boolean(*b)),
+      LuaValue::Integer(i) => Some(Self::Integer(*i)),
+      LuaValue::Number(n) => Some(Self::Number(*n)),
+      LuaValue::String(s) => Some(Self::String(s.to_string_lossy())),
+      LuaValue::Table(t) if depth < MAX_DEPTH => {
+        let mut pairs = Vec::new();
+        for pair in t.pairs::<LuaValue, LuaValue>() {
+          let (key, value) = pair?;
+          if let (Some(key), Some(value)) = (
+            Self::from_lua(&key, depth + 1)?,
+            Self::from_lua(&value, depth + 1)?,
+          ) {
+            pairs.push((key, value));
+          }
+        }
+        Some(Self::Table(pairs))
+      }
+      _ => None,
+    })
+  }
+
+  fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+    Ok(match self {
+      Self::Boolean(b) => LuaValue::Boolean(b),
+      Self::Integer(i) => LuaValue::Integer(i),
+      Self::Number(n) => LuaValue::Number(n),
+      Self::String(s) => LuaValue::String(lua.create_string(s)?),
+      Self::Table(pairs) => {
+        let t = lua.create_table()?;
+        for (key, value) in pairs {
+          t.raw_set(key.into_lua(lua)?, value.into_lua(lua)?)?;
+        }
+        LuaValue::Table(t)
+      }
+    })
+  }
+}
+
+/// persisted keys of a layout, by component path
+type PersistedStates = HashMap<Vec<usize>, HashMap<String, PersistedValue>>;
+
+/// state of a component instance
+struct ComponentState {
+  /// `widget` function of the component, so moved or replaced components don't get the state of another one
+  function: LuaFunction,
+  table: LuaTable,
+  persist: Vec<String>,
+}
+
+/// state of every component of the loaded layout, by path
+#[derive(Default)]
+struct States {
+  /// file of the loaded layout, `None` if no layout was loaded from a file
+  layout: Option<PathBuf>,
+  persisted: PersistedStates,
+  components: HashMap<Vec<usize>, ComponentState>,
+}
+
+/// FNV-1a hash of a layout path, stable across builds unlike the std hasher
+fn path_hash(path: &Path) -> u64 {
+  path
+    .to_string_lossy()
+    .bytes()
+    .fold(0xcbf29ce484222325, |hash, byte| {
+      (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// file the persisted state of a layout is saved to
+///
+/// keyed by the layout file, so layouts sharing a name don't overwrite each other's state
+fn states_path(layout: &Path) -> Result<PathBuf> {
+  let mut path = dirs::data_dir().ok_or(anyhow::Error::msg("couldn't get data directory"))?;
+  let stem = layout
+    .file_stem()
+    .map(|s| sanitize(&s.to_string_lossy()))
+    .unwrap_or_default();
+  path.push("yast/state");
+  path.push(format!("{}-{:016x}.ron", stem, path_hash(layout)));
+  Ok(path)
+}
+
+fn load_persisted(layout: &Path) -> Result<PersistedStates> {
+  let path = states_path(layout)?;
+  if !path.try_exists()? {
+    return Ok(HashMap::new());
+  }
+  Ok(ron::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// forgets the state of every component, and reads the persisted state of a newly loaded layout
+///
+/// layouts that weren't loaded from a file don't persist anything
+pub fn reset_states(lua: &Lua, layout: Option<&Path>) {
+  // the same file opened through another path keeps its state
+  let layout = layout.map(|l| fs::canonicalize(l).unwrap_or_else(|_| l.to_path_buf()));
+  let persisted = match &layout {
+    Some(layout) => load_persisted(layout).unwrap_or_else(|err| {
+      error!(
+        "couldn't load persisted state of layout {}: {}",
+        layout.display(),
+        err
+      );
+      HashMap::new()
+    }),
+    None => HashMap::new(),
+  };
+  lua.set_app_data(States {
+    layout,
+    persisted,
+    components: HashMap::new(),
+  });
+}
+
+/// state table of the component at `path`, created with its persisted keys on the first build
+pub(crate) fn state_table(
+  lua: &Lua,
+  path: &Vec<usize>,
+  function: &LuaFunction,
+  persist: &[String],
+) -> LuaResult<LuaTable> {
+  if lua.app_data_ref::<States>().is_none() {
+    lua.set_app_data(States::default());
+  }
+  let mut states = lua
+    .app_data_mut::<States>()
+    .ok_or_else(|| LuaError::runtime("couldn't get component states"))?;
+
+  if let Some(state) = states.components.get(path) {
+    if state.function == *function {
+      return Ok(state.table.clone());
+    }
+  }
+
+  let table = lua.create_table()?;
+  if let Some(persisted) = states.persisted.get(path) {
+    for key in persist {
+      if let Some(value) = persisted.get(key) {
+        table.raw_set(key.as_str(), value.clone().into_lua(lua)?)?;
+      }
+    }
+  }
+  states.components.insert(
+    path.clone(),
+    ComponentState {
+      function: function.clone(),
+      table: table.clone(),
+      persist: persist.to_vec(),
+    },
+  );
+  Ok(table)
+}
+
+/// saves the persisted keys of every component of the loaded layout
+pub fn save_states(lua: &Lua) -> Result<()> {
+  let Some(mut states) = lua.app_data_mut::<States>() else {
+    return Ok(());
+  };
+  let Some(layout) = states.layout.clone() else {
+    return Ok(());
+  };
+
+  // components that weren't built since the layout was loaded keep their persisted state
+  let mut updates = Vec::new();
+  for (path, state) in &states.components {
+    if state.persist.is_empty() {
+      continue;
+    }
+    let mut values = HashMap::new();
+    for key in &state.persist {
+      if let Some(value) = PersistedValue::from_lua(&state.table.raw_get(key.as_str())?, 0)? {
+        values.insert(key.clone(), value);
+      }
+    }
+    updates.push((path.clone(), values));
+  }
+  if updates.is_empty() {
+    return Ok(());
+  }
+  for (path, values) in updates {
+    if values.is_empty() {
+      states.persisted.remove(&path);
+    } else {
+      states.persisted.insert(path, values);
+    }
+  }
+
+  let path = states_path(&layout)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(path, ron::to_string(&states.persisted)?)?;
+  Ok(())
+}
@@ -1,6 +1,14 @@
 //! `yast-core` is YASX's main lib.
 
+pub mod comparisons;
 pub mod defaults;
+pub mod export;
 pub mod layout;
 pub mod lua;
+pub mod marathon;
+mod paths;
+pub mod practice;
 pub mod repository;
+pub mod reset_reasons;
+pub mod statistics;
+pub mod subsplits;
@@ -0,0 +1,163 @@
+//! execution time budget of component code
+//!
+//! component functions are aborted when they run for too long, so an infinite loop can't freeze the timer.
+//! components exceeding their budget too many times are disabled until the layout is reloaded.
+//! protected calls can't catch the error of an exceeded budget, see [`protected_calls`]
+
+use std::{
+  collections::HashMap,
+  fmt::Display,
+  time::{Duration, Instant},
+};
+
+use mlua::{HookTriggers, VmState, prelude::*};
+
+/// time a single call of component code can run for
+pub const BUDGET: Duration = Duration::from_millis(50);
+/// number of exceeded budgets after which a component is disabled
+pub const MAX_STRIKES: u32 = 3;
+/// number of instructions between two checks of the elapsed time
+const CHECK_INTERVAL: u32 = 1000;
+
+/// error returned when lua code exceeds its budget
+#[derive(Debug)]
+pub struct BudgetExceeded;
+
+impl Display for BudgetExceeded {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "exceeded its time budget of {} ms", BUDGET.as_millis())
+  }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// number of exceeded budgets, by component path
+#[derive(Default)]
+struct Strikes(HashMap<Vec<usize>, u32>);
+
+/// whether the budget of the running code was exceeded, only present while code runs with a budget
+struct Exceeded(bool);
+
+fn is_exceeded(lua: &Lua) -> bool {
+  lua.app_data_ref::<Exceeded>().map(|e| e.0).unwrap_or(false)
+}
+
+/// runs lua code, aborting it if it runs for longer than [`BUDGET`]
+pub fn run_with_budget<R>(lua: &Lua, f: impl FnOnce() -> LuaResult<R>) -> LuaResult<R> {
+  let start = Instant::now();
+  lua.set_app_data(Exceeded(false));
+
+  lua.set_hook(
+    HookTriggers::new().every_nth_instruction(CHECK_INTERVAL),
+    move |lua, _| {
+      if start.elapsed() > BUDGET {
+        lua.set_app_data(Exceeded(true));
+        return Err(LuaError::external(BudgetExceeded));
+      }
+      Ok(VmState::Continue)
+    },
+  );
+  let result = f();
+  lua.remove_hook();
+
+  // the error raised by the hook may have been caught and replaced by the lua code
+  let exceeded = is_exceeded(lua);
+  lua.remove_app_data::<Exceeded>();
+  if exceeded {
+    return Err(LuaError::external(BudgetExceeded));
+  }
+  result
+}
+
+/// creates `pcall`, `xpcall` and `coroutine.resume`, raising the error again once the budget is exceeded
+///
+/// otherwise, code catching the error in a loop would never return
+const PROTECTED_CALLS: &str = r#"
+local pcall, xpcall, resume, check_budget = ...
+local function checked(...)
+  check_budget()
+  return ...
+end
+return {
+  pcall = function(...)
+    return checked(pcall(...))
+  end,
+  xpcall = function(...)
+    return checked(xpcall(...))
+  end,
+  resume = function(...)
+    return checked(resume(...))
+  end,
+}
+"#;
+
+/// protected call functions replacing the ones of the api, see [`PROTECTED_CALLS`]
+pub(crate) fn protected_calls(lua: &Lua) -> LuaResult<LuaTable> {
+  let globals = lua.globals();
+  let check_budget = lua.create_function(|lua, ()| {
+    if is_exceeded(lua) {
+      return Err(LuaError::external(BudgetExceeded));
+    }
+    Ok(())
+  })?;
+  lua.load(PROTECTED_CALLS).set_name("protected_calls").call((
+    globals.raw_get::<LuaFunction>("pcall")?,
+    globals.raw_get::<LuaFunction>("xpcall")?,
+    globals
+      .raw_get::<LuaTable>("coroutine")?
+      .raw_get::<LuaFunction>("resume")?,
+    check_budget,
+  ))
+}
+
+/// whether an error comes from an exceeded budget
+pub fn is_budget_exceeded(err: &LuaError) -> bool {
+  match err {
+    LuaError::ExternalError(e) => e.downcast_ref::<BudgetExceeded>().is_some(),
+    LuaError::CallbackError { cause, .. } => is_budget_exceeded(cause),
+    _ => false,
+  }
+}
+
+/// counts an exceeded budget for the component at `path`
+pub(crate) fn strike(lua: &Lua, path: &Vec<usize>) {
+  if lua.app_data_ref::<Strikes>().is_none() {
+    lua.set_app_data(Strikes::default());
+  }
+  if let Some(mut strikes) = lua.app_data_mut::<Strikes>() {
+    *strikes.0.entry(path.clone()).or_insert(0) += 1;
+  }
+}
+
+/// whether the component at `path` exceeded its budget too many times
+pub(crate) fn is_disabled(lua: &Lua, path: &Vec<usize>) -> bool {
+  lua
+    .app_data_ref::<Strikes>()
+    .and_then(|s| s.0.get(path).copied())
+    .map(|s| s >= MAX_STRIKES)
+    .unwrap_or(false)
+}
+
+/// forgets every exceeded budget, enabling the disabled components again
+pub fn reset_strikes(lua: &Lua) {
+  lua.remove_app_data::<Strikes>();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lua::{LuaContext, sandbox::environment};
+
+  #[test]
+  fn caught_budget_errors_are_raised_again() {
+    let lua = LuaContext::init().unwrap().lua;
+    let env = environment(&lua).unwrap();
+    let result = run_with_budget(&lua, || {
+      lua
+        .load("while true do pcall(function() while true do end end) end")
+        .set_environment(env)
+        .exec()
+    });
+    assert!(result.is_err_and(|err| is_budget_exceeded(&err)));
+  }
+}
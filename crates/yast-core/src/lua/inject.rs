@@ -1,45 +1,240 @@
 //! general value injection
 
+use std::{
+  collections::HashMap,
+  hash::{DefaultHasher, Hash, Hasher},
+  time::Instant,
+};
+
 use anyhow::Result;
-use livesplit_core::{Timer, analysis};
+use livesplit_core::{Run, TimeSpan, Timer, TimerPhase, TimingMethod, analysis, timing::Snapshot};
 use mlua::prelude::*;
 
-use crate::{lua::widgets::image::ImageHandleLua, repository::Repository};
+use crate::{lua::widgets::image::ImageHandleLua, repository::Repository, subsplits::Subsplits};
 
-/// general value injector
-pub fn inject_values_in_lua(lua: &Lua, timer: &Timer, repository: &Repository) -> Result<()> {
-  let snapshot = timer.snapshot();
+/// attempt history and segment history tables
+struct HistoryTables {
+  attempts: LuaTable,
+  segments: Vec<LuaTable>,
+}
 
-  let current_attempt_duration = snapshot.current_attempt_duration().total_seconds();
-  let current_comparison = snapshot.current_comparison();
-  let current_phase = format!("{:?}", snapshot.current_phase());
-  let current_split = snapshot.current_split_index().map(|f| f + 1);
-  let current_time = snapshot.current_time();
-  let current_timing_method = format!("{:?}", snapshot.current_timing_method());
+impl HistoryTables {
+  /// cheap fingerprint of the run's history
+  ///
+  /// segment histories only change along with the attempt history when an attempt is reset,
+  /// anything else modifying them must call [`invalidate_value_cache`]
+  fn key(run: &Run) -> (usize, Option<i32>, usize) {
+    (
+      run.attempt_history().len(),
+      run.attempt_history().last().map(|a| a.index()),
+      run.len(),
+    )
+  }
 
-  let snapshot_table = lua.create_table()?;
+  fn build(lua: &Lua, run: &Run) -> Result<Self> {
+    let attempts_table = lua.create_table()?;
+    for attempt in run.attempt_history() {
+      let attempt_table = lua.create_table()?;
+      attempt_table.set("index", attempt.index())?;
+      attempt_table.set(
+        "started",
+        attempt.started().map(|d| d.time.unix_timestamp()),
+      )?;
+      attempt_table.set("ended", attempt.ended().map(|d| d.time.unix_timestamp()))?;
+      attempt_table.set("duration", attempt.duration().map(|d| d.total_seconds()))?;
+      attempt_table.set(
+        "pause_time",
+        attempt.pause_time().map(|d| d.total_seconds()),
+      )?;
+      let time_table = lua.create_table()?;
+      time_table.set(
+        "real_time",
+        attempt.time().real_time.map(|t| t.total_seconds()),
+      )?;
+      time_table.set(
+        "game_time",
+        attempt.time().game_time.map(|t| t.total_seconds()),
+      )?;
+      attempt_table.set("time", time_table)?;
+      attempts_table.push(attempt_table)?;
+    }
+
+    let mut segments = Vec::new();
+    for segment in run.segments() {
+      let history_table = lua.create_table()?;
+      for (index, time) in segment.segment_history() {
+        let entry_table = lua.create_table()?;
+        entry_table.set("attempt", *index)?;
+        entry_table.set("real_time", time.real_time.map(|t| t.total_seconds()))?;
+        entry_table.set("game_time", time.game_time.map(|t| t.total_seconds()))?;
+        history_table.push(entry_table)?;
+      }
+      segments.push(history_table);
+    }
+
+    Ok(Self {
+      attempts: attempts_table,
+      segments,
+    })
+  }
+}
+
+/// fingerprint of the run data published in the `run` table
+///
+/// the history, names, comparison names and metadata are covered, but not the split times of the comparisons,
+/// which only change along with the history or when the run is edited: callers modifying the run outside of
+/// timer operations must call [`invalidate_value_cache`]
+fn run_key(run: &Run) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  run.game_name().hash(&mut hasher);
+  run.category_name().hash(&mut hasher);
+  run.attempt_count().hash(&mut hasher);
+  HistoryTables::key(run).hash(&mut hasher);
+  for comparison in run.comparisons() {
+    comparison.hash(&mut hasher);
+  }
+  let metadata = run.metadata();
+  metadata.run_id().hash(&mut hasher);
+  metadata.platform_name().hash(&mut hasher);
+  metadata.region_name().hash(&mut hasher);
+  metadata.uses_emulator().hash(&mut hasher);
+  for (name, value) in metadata.speedrun_com_variables() {
+    name.hash(&mut hasher);
+    value.hash(&mut hasher);
+  }
+  for (name, variable) in metadata.custom_variables() {
+    name.hash(&mut hasher);
+    variable.value.hash(&mut hasher);
+  }
+  for segment in run.segments() {
+    segment.name().hash(&mut hasher);
+  }
+  hasher.finish()
+}
+
+/// state of an attempt that most analysis values depend on, everything else being the current time
+#[derive(PartialEq)]
+struct SplitKey {
+  run: u64,
+  phase: TimerPhase,
+  current_split: Option<usize>,
+}
+
+impl SplitKey {
+  fn new(run_key: u64, timer: &Timer) -> Self {
+    Self {
+      run: run_key,
+      phase: timer.current_phase(),
+      current_split: timer.current_split_index(),
+    }
+  }
+
+  /// segment being run, whose values change every frame
+  fn live_segment(&self, run: &Run) -> Option<usize> {
+    match self.phase {
+      TimerPhase::Running | TimerPhase::Paused => self.current_split.filter(|i| *i < run.len()),
+      _ => None,
+    }
+  }
+}
+
+/// cached `run` and `analysis` tables of a timer
+///
+/// the `run` table is rebuilt when the run changes, and the `analysis` table on splits,
+/// so only the values depending on the current time are refreshed every frame
+struct TimerCache {
+  run_key: u64,
+  run: LuaRegistryKey,
+  comparison_names: Vec<String>,
+  split_key: SplitKey,
+  analysis: LuaRegistryKey,
+  /// possible time save of the live segment when the analysis was built, by comparison
+  live_possible_time_save: Vec<f64>,
+  /// total possible time saves of the segments up to the live one when the analysis was built, by comparison
+  total_possible_time_saves: Vec<Vec<f64>>,
+}
+
+/// caches of every timer, by timer index
+#[derive(Default)]
+struct TimerCaches(HashMap<usize, TimerCache>);
+
+/// forces the `run` and `analysis` tables to be rebuilt on next injection
+///
+/// must be called when the run is replaced or modified outside of timer operations,
+/// as split times and icons aren't part of the run's fingerprint
+pub fn invalidate_value_cache(lua: &Lua) {
+  lua.remove_app_data::<TimerCaches>();
+}
 
-  snapshot_table.set("current_attempt_duration", current_attempt_duration)?;
-  snapshot_table.set("current_comparison", current_comparison)?;
-  snapshot_table.set("current_phase", current_phase)?;
-  snapshot_table.set("current_split", current_split)?;
-  snapshot_table.set("current_timing_method", current_timing_method)?;
+fn timing_table(lua: &Lua, real_time: Option<f64>, game_time: Option<f64>) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  table.set("real_time", real_time)?;
+  table.set("game_time", game_time)?;
+  Ok(table)
+}
 
-  let current_time_table = lua.create_table()?;
-  current_time_table.set(
-    "real_time",
-    current_time.real_time.map(|f| f.total_seconds()),
+/// table of a value for both timing methods
+fn timing_methods_table<T: IntoLua>(lua: &Lua, f: impl Fn(TimingMethod) -> T) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  table.set("real_time", f(TimingMethod::RealTime))?;
+  table.set("game_time", f(TimingMethod::GameTime))?;
+  Ok(table)
+}
+
+fn live_table(lua: &Lua, name: &str, value: impl IntoLua, is_live: bool) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  table.set(name, value)?;
+  table.set("is_live", is_live)?;
+  Ok(table)
+}
+
+fn seconds(t: Option<TimeSpan>) -> Option<f64> {
+  t.map(|t| t.total_seconds())
+}
+
+fn build_snapshot_table(lua: &Lua, snapshot: &Snapshot) -> Result<LuaTable> {
+  let snapshot_table = lua.create_table()?;
+
+  snapshot_table.set(
+    "current_attempt_duration",
+    snapshot.current_attempt_duration().total_seconds(),
+  )?;
+  snapshot_table.set("current_comparison", snapshot.current_comparison())?;
+  snapshot_table.set("current_phase", format!("{:?}", snapshot.current_phase()))?;
+  snapshot_table.set(
+    "current_split",
+    snapshot.current_split_index().map(|f| f + 1),
   )?;
-  current_time_table.set(
-    "game_time",
-    current_time.game_time.map(|f| f.total_seconds()),
+  snapshot_table.set(
+    "current_timing_method",
+    format!("{:?}", snapshot.current_timing_method()),
+  )?;
+
+  let current_time = snapshot.current_time();
+  snapshot_table.set(
+    "current_time",
+    timing_table(
+      lua,
+      seconds(current_time.real_time),
+      seconds(current_time.game_time),
+    )?,
   )?;
-  snapshot_table.set("current_time", current_time_table)?;
 
-  lua.globals().set("snapshot", snapshot_table)?;
+  // time left before the timer reaches 0, while running with a negative offset
+  let countdown = seconds(current_time.real_time)
+    .filter(|t| *t < 0.)
+    .map(|t| -t);
+  snapshot_table.set("countdown", countdown)?;
 
-  let run = timer.run();
+  Ok(snapshot_table)
+}
 
+fn build_run_table(
+  lua: &Lua,
+  run: &Run,
+  repository: &Repository,
+  comparison_names: &[String],
+) -> Result<LuaTable> {
   let run_table = lua.create_table()?;
 
   run_table.set("game_name", run.game_name())?;
@@ -56,20 +251,32 @@ pub fn inject_values_in_lua(lua: &Lua, timer: &Timer, repository: &Repository) -
   metadata_table.set("platform_name", metadata.platform_name())?;
   metadata_table.set("uses_emulator", metadata.uses_emulator())?;
   metadata_table.set("region_name", metadata.region_name())?;
+  let speedrun_com_variables_table = lua.create_table()?;
+  for (name, value) in metadata.speedrun_com_variables() {
+    speedrun_com_variables_table.set(name, value)?;
+  }
+  metadata_table.set("speedrun_com_variables", speedrun_com_variables_table)?;
+  let custom_variables_table = lua.create_table()?;
+  for (name, variable) in metadata.custom_variables() {
+    custom_variables_table.set(name, variable.value.as_str())?;
+  }
+  metadata_table.set("custom_variables", custom_variables_table)?;
   run_table.set("metadata", metadata_table)?;
 
-  let mut comparison_names: Vec<String> = vec!["Personal Best".to_string()];
-  comparison_names.extend(
-    run
-      .comparisons()
-      .map(|f| f.to_string())
-      .collect::<Vec<String>>(),
-  );
+  let history_tables = HistoryTables::build(lua, run)?;
+  run_table.set("attempts", history_tables.attempts)?;
+
+  let subsplits = Subsplits::parse(run.segments().iter().map(|s| s.name()));
 
   let segments_table = lua.create_table()?;
   for (i, segment) in run.segments().iter().enumerate() {
     let segment_table = lua.create_table()?;
     segment_table.set("name", segment.name())?;
+    if let Some(subsplit) = subsplits.segments.get(i) {
+      segment_table.set("group", subsplit.group + 1)?;
+      segment_table.set("is_group_end", subsplit.is_group_end)?;
+      segment_table.set("display_name", subsplit.display_name.as_str())?;
+    }
     segment_table.set(
       "icon",
       repository
@@ -81,164 +288,101 @@ pub fn inject_values_in_lua(lua: &Lua, timer: &Timer, repository: &Repository) -
     )?;
 
     let comparisons_table = lua.create_table()?;
-    for comp_name in &comparison_names {
+    for comp_name in comparison_names {
       let comp_time = segment.comparison(comp_name);
-      let comp_table = lua.create_table()?;
-      comp_table.set("real_time", comp_time.real_time.map(|t| t.total_seconds()))?;
-      comp_table.set("game_time", comp_time.game_time.map(|t| t.total_seconds()))?;
-      comparisons_table.set(comp_name.as_str(), comp_table)?;
+      comparisons_table.set(
+        comp_name.as_str(),
+        timing_table(
+          lua,
+          seconds(comp_time.real_time),
+          seconds(comp_time.game_time),
+        )?,
+      )?;
     }
     segment_table.set("comparisons", comparisons_table)?;
 
+    if let Some(history_table) = history_tables.segments.get(i) {
+      segment_table.set("history", history_table)?;
+    }
+
     segments_table.set(i + 1, segment_table)?;
   }
   run_table.set("segments", segments_table)?;
 
-  lua.globals().set("run", run_table)?;
+  let groups_table = lua.create_table()?;
+  for group in &subsplits.groups {
+    let group_table = lua.create_table()?;
+    group_table.set("name", group.name.as_str())?;
+    group_table.set("first_segment", group.first_segment + 1)?;
+    group_table.set("last_segment", group.last_segment + 1)?;
+    group_table.set("has_subsplits", group.has_subsplits())?;
+    groups_table.push(group_table)?;
+  }
+  run_table.set("groups", groups_table)?;
+
+  Ok(run_table)
+}
 
+/// builds the whole `analysis` table, live values included
+fn build_analysis_table(
+  lua: &Lua,
+  timer: &Timer,
+  snapshot: &Snapshot,
+  comparison_names: &[String],
+) -> Result<LuaTable> {
+  let run = timer.run();
   let analysis_table = lua.create_table()?;
 
   let analysis_comparisons_table = lua.create_table()?;
-  for comp_name in &comparison_names {
+  for comp_name in comparison_names {
     let comp_table = lua.create_table()?;
 
-    let current_pace = analysis::current_pace::calculate(&snapshot, comp_name);
-    let current_pace_table = lua.create_table()?;
-    current_pace_table.set("time", current_pace.0.map(|f| f.total_seconds()))?;
-    current_pace_table.set("is_live", current_pace.1)?;
-    comp_table.set("current_pace", current_pace_table)?;
-
-    let delta = analysis::delta::calculate(&snapshot, comp_name);
-    let delta_table = lua.create_table()?;
-    delta_table.set("delta", delta.0.map(|f| f.total_seconds()))?;
-    delta_table.set("is_live", delta.1)?;
-    comp_table.set("delta", delta_table)?;
-
-    let live_delta_real_time = analysis::state_helper::check_live_delta(
-      &snapshot,
-      false,
-      comp_name,
-      livesplit_core::TimingMethod::RealTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_delta_game_time = analysis::state_helper::check_live_delta(
-      &snapshot,
-      false,
-      comp_name,
-      livesplit_core::TimingMethod::GameTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_delta_table = lua.create_table()?;
-    live_delta_table.set("real_time", live_delta_real_time)?;
-    live_delta_table.set("game_time", live_delta_game_time)?;
-    analysis_table.set("live_delta", live_delta_table)?;
-
-    let live_split_delta_real_time = analysis::state_helper::check_live_delta(
-      &snapshot,
-      true,
-      comp_name,
-      livesplit_core::TimingMethod::RealTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_split_delta_game_time = analysis::state_helper::check_live_delta(
-      &snapshot,
-      true,
-      comp_name,
-      livesplit_core::TimingMethod::GameTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_split_delta_table = lua.create_table()?;
-    live_split_delta_table.set("real_time", live_split_delta_real_time)?;
-    live_split_delta_table.set("game_time", live_split_delta_game_time)?;
-    analysis_table.set("live_split_delta", live_split_delta_table)?;
-
     let segments_table = lua.create_table()?;
-    for (i, _segment) in run.segments().iter().enumerate() {
+    for i in 0..run.len() {
       let segment_table = lua.create_table()?;
 
-      let pst = analysis::possible_time_save::calculate(&snapshot, i, comp_name, true);
-      let pst_table = lua.create_table()?;
-      pst_table.set("time", pst.0.map(|f| f.total_seconds()))?;
-      pst_table.set("is_live", pst.1)?;
-      segment_table.set("possible_save_time", pst_table)?;
-
-      let total_pst = analysis::possible_time_save::calculate_total(&snapshot, i, comp_name);
-      let total_pst_table = lua.create_table()?;
-      total_pst_table.set("time", total_pst.0.total_seconds())?;
-      total_pst_table.set("is_live", total_pst.1)?;
-      segment_table.set("total_possible_save_time", total_pst_table)?;
-
-      let is_best_segment_real_time = analysis::state_helper::check_best_segment(
-        &timer,
-        i,
-        livesplit_core::TimingMethod::RealTime,
-      );
-      let is_best_segment_game_time = analysis::state_helper::check_best_segment(
-        &timer,
-        i,
-        livesplit_core::TimingMethod::GameTime,
-      );
-      let is_best_segment_table = lua.create_table()?;
-      is_best_segment_table.set("real_time", is_best_segment_real_time)?;
-      is_best_segment_table.set("game_time", is_best_segment_game_time)?;
-      segment_table.set("is_best_segment", is_best_segment_table)?;
-
-      let last_delta_real_time = analysis::state_helper::last_delta(
-        timer.run(),
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::RealTime,
-      )
-      .map(|f| f.total_seconds());
-      let last_delta_game_time = analysis::state_helper::last_delta(
-        timer.run(),
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::GameTime,
-      )
-      .map(|f| f.total_seconds());
-      let last_delta_table = lua.create_table()?;
-      last_delta_table.set("real_time", last_delta_real_time)?;
-      last_delta_table.set("game_time", last_delta_game_time)?;
-      segment_table.set("last_delta", last_delta_table)?;
-
-      let live_segment_delta_real_time = analysis::state_helper::live_segment_delta(
-        &snapshot,
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::RealTime,
-      )
-      .map(|f| f.total_seconds());
-      let live_segment_delta_game_time = analysis::state_helper::live_segment_delta(
-        &snapshot,
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::GameTime,
-      )
-      .map(|f| f.total_seconds());
-      let live_segment_delta_table = lua.create_table()?;
-      live_segment_delta_table.set("real_time", live_segment_delta_real_time)?;
-      live_segment_delta_table.set("game_time", live_segment_delta_game_time)?;
-      segment_table.set("live_segment_delta", live_segment_delta_table)?;
-
-      let previous_segment_delta_real_time = analysis::state_helper::previous_segment_delta(
-        &snapshot,
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::RealTime,
-      )
-      .map(|f| f.total_seconds());
-      let previous_segment_delta_game_time = analysis::state_helper::previous_segment_delta(
-        &snapshot,
-        i,
-        comp_name,
-        livesplit_core::TimingMethod::GameTime,
-      )
-      .map(|f| f.total_seconds());
-      let previous_segment_delta_table = lua.create_table()?;
-      previous_segment_delta_table.set("real_time", previous_segment_delta_real_time)?;
-      previous_segment_delta_table.set("game_time", previous_segment_delta_game_time)?;
-      segment_table.set("previous_segment_delta", previous_segment_delta_table)?;
+      let pst = analysis::possible_time_save::calculate(snapshot, i, comp_name, true);
+      segment_table.set(
+        "possible_save_time",
+        live_table(lua, "time", seconds(pst.0), pst.1)?,
+      )?;
+
+      let total_pst = analysis::possible_time_save::calculate_total(snapshot, i, comp_name);
+      segment_table.set(
+        "total_possible_save_time",
+        live_table(lua, "time", total_pst.0.total_seconds(), total_pst.1)?,
+      )?;
+
+      segment_table.set(
+        "is_best_segment",
+        timing_methods_table(lua, |method| {
+          analysis::state_helper::check_best_segment(timer, i, method)
+        })?,
+      )?;
+      segment_table.set(
+        "last_delta",
+        timing_methods_table(lua, |method| {
+          seconds(analysis::state_helper::last_delta(
+            run, i, comp_name, method,
+          ))
+        })?,
+      )?;
+      segment_table.set(
+        "live_segment_delta",
+        timing_methods_table(lua, |method| {
+          seconds(analysis::state_helper::live_segment_delta(
+            snapshot, i, comp_name, method,
+          ))
+        })?,
+      )?;
+      segment_table.set(
+        "previous_segment_delta",
+        timing_methods_table(lua, |method| {
+          seconds(analysis::state_helper::previous_segment_delta(
+            snapshot, i, comp_name, method,
+          ))
+        })?,
+      )?;
 
       segments_table.set(i + 1, segment_table)?;
     }
@@ -249,93 +393,337 @@ pub fn inject_values_in_lua(lua: &Lua, timer: &Timer, repository: &Repository) -
   analysis_table.set("comparisons", analysis_comparisons_table)?;
 
   let segments_table = lua.create_table()?;
-  for (i, _segment) in run.segments().iter().enumerate() {
+  for i in 0..run.len() {
     let segment_table = lua.create_table()?;
-
-    let live_segment_time_real_time = analysis::state_helper::live_segment_time(
-      &snapshot,
-      i,
-      livesplit_core::TimingMethod::RealTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_segment_time_game_time = analysis::state_helper::live_segment_time(
-      &snapshot,
-      i,
-      livesplit_core::TimingMethod::GameTime,
-    )
-    .map(|f| f.total_seconds());
-    let live_segment_time_table = lua.create_table()?;
-    live_segment_time_table.set("real_time", live_segment_time_real_time)?;
-    live_segment_time_table.set("game_time", live_segment_time_game_time)?;
-    segment_table.set("live_segment_time", live_segment_time_table)?;
-
-    let previous_segment_time_real_time = analysis::state_helper::previous_segment_time(
-      &snapshot,
-      i,
-      livesplit_core::TimingMethod::RealTime,
-    )
-    .map(|f| f.total_seconds());
-    let previous_segment_time_game_time = analysis::state_helper::previous_segment_time(
-      &snapshot,
-      i,
-      livesplit_core::TimingMethod::GameTime,
-    )
-    .map(|f| f.total_seconds());
-    let previous_segment_time_table = lua.create_table()?;
-    previous_segment_time_table.set("real_time", previous_segment_time_real_time)?;
-    previous_segment_time_table.set("game_time", previous_segment_time_game_time)?;
-    segment_table.set("previous_segment_time", previous_segment_time_table)?;
-
+    segment_table.set(
+      "live_segment_time",
+      timing_methods_table(lua, |method| {
+        seconds(analysis::state_helper::live_segment_time(
+          snapshot, i, method,
+        ))
+      })?,
+    )?;
+    segment_table.set(
+      "previous_segment_time",
+      timing_methods_table(lua, |method| {
+        seconds(analysis::state_helper::previous_segment_time(
+          snapshot, i, method,
+        ))
+      })?,
+    )?;
     segments_table.set(i + 1, segment_table)?;
   }
   analysis_table.set("segments", segments_table)?;
 
-  let pb_chance = analysis::pb_chance::for_timer(&snapshot);
-  let pb_chance_table = lua.create_table()?;
-  pb_chance_table.set("chance", pb_chance.0)?;
-  pb_chance_table.set("is_live", pb_chance.1)?;
-  analysis_table.set("pb_chance", pb_chance_table)?;
-
-  let sum_of_best_segments_real_time = analysis::sum_of_segments::calculate_best(
-    timer.run().segments(),
-    false,
-    false,
-    livesplit_core::TimingMethod::RealTime,
-  )
-  .map(|f| f.total_seconds());
-  let sum_of_best_segments_game_time = analysis::sum_of_segments::calculate_best(
-    timer.run().segments(),
-    false,
-    false,
-    livesplit_core::TimingMethod::GameTime,
-  )
-  .map(|f| f.total_seconds());
-  let sum_of_best_segments_table = lua.create_table()?;
-  sum_of_best_segments_table.set("real_time", sum_of_best_segments_real_time)?;
-  sum_of_best_segments_table.set("game_time", sum_of_best_segments_game_time)?;
-  analysis_table.set("sum_of_best_segments", sum_of_best_segments_table)?;
-
-  let sum_of_worst_segments_real_time = analysis::sum_of_segments::calculate_worst(
-    timer.run().segments(),
-    false,
-    livesplit_core::TimingMethod::RealTime,
-  )
-  .map(|f| f.total_seconds());
-  let sum_of_worst_segments_game_time = analysis::sum_of_segments::calculate_worst(
-    timer.run().segments(),
-    false,
-    livesplit_core::TimingMethod::GameTime,
-  )
-  .map(|f| f.total_seconds());
-  let sum_of_worst_segments_table = lua.create_table()?;
-  sum_of_worst_segments_table.set("real_time", sum_of_worst_segments_real_time)?;
-  sum_of_worst_segments_table.set("game_time", sum_of_worst_segments_game_time)?;
-  analysis_table.set("sum_of_worst_segments", sum_of_worst_segments_table)?;
-
-  let total_playtime = analysis::total_playtime::calculate(&timer).total_seconds();
-  analysis_table.set("total_playtime", total_playtime)?;
-
-  lua.globals().set("analysis", analysis_table)?;
+  analysis_table.set(
+    "sum_of_best_segments",
+    timing_methods_table(lua, |method| {
+      seconds(analysis::sum_of_segments::calculate_best(
+        run.segments(),
+        false,
+        false,
+        method,
+      ))
+    })?,
+  )?;
+  analysis_table.set(
+    "sum_of_worst_segments",
+    timing_methods_table(lua, |method| {
+      seconds(analysis::sum_of_segments::calculate_worst(
+        run.segments(),
+        false,
+        method,
+      ))
+    })?,
+  )?;
+
+  Ok(analysis_table)
+}
+
+/// refreshes the values of the `analysis` table depending on the current time
+fn refresh_live_values(
+  lua: &Lua,
+  analysis_table: &LuaTable,
+  cache: &TimerCache,
+  timer: &Timer,
+  snapshot: &Snapshot,
+) -> Result<()> {
+  let live_segment = cache.split_key.live_segment(timer.run());
+
+  let analysis_comparisons_table = analysis_table.get::<LuaTable>("comparisons")?;
+  for (c, comp_name) in cache.comparison_names.iter().enumerate() {
+    let comp_table = analysis_comparisons_table.get::<LuaTable>(comp_name.as_str())?;
+
+    let current_pace = analysis::current_pace::calculate(snapshot, comp_name);
+    comp_table.set(
+      "current_pace",
+      live_table(lua, "time", seconds(current_pace.0), current_pace.1)?,
+    )?;
+
+    let delta = analysis::delta::calculate(snapshot, comp_name);
+    comp_table.set(
+      "delta",
+      live_table(lua, "delta", seconds(delta.0), delta.1)?,
+    )?;
+
+    let Some(live) = live_segment else {
+      continue;
+    };
+    let segments_table = comp_table.get::<LuaTable>("segments")?;
+    let segment_table = segments_table.get::<LuaTable>(live + 1)?;
+
+    let pst = analysis::possible_time_save::calculate(snapshot, live, comp_name, true);
+    segment_table.set(
+      "possible_save_time",
+      live_table(lua, "time", seconds(pst.0), pst.1)?,
+    )?;
+
+    // totals up to the live segment include its possible time save, and nothing else changes
+    let pst_change = seconds(pst.0).unwrap_or(0.) - cache.live_possible_time_save[c];
+    for (i, total) in cache.total_possible_time_saves[c].iter().enumerate() {
+      segments_table.get::<LuaTable>(i + 1)?.set(
+        "total_possible_save_time",
+        live_table(lua, "time", total + pst_change, pst.1)?,
+      )?;
+    }
+
+    segment_table.set(
+      "live_segment_delta",
+      timing_methods_table(lua, |method| {
+        seconds(analysis::state_helper::live_segment_delta(
+          snapshot, live, comp_name, method,
+        ))
+      })?,
+    )?;
+  }
+
+  // the live deltas are those of the last comparison
+  let last_comparison = cache
+    .comparison_names
+    .last()
+    .map(|c| c.as_str())
+    .unwrap_or("Personal Best");
+  analysis_table.set(
+    "live_delta",
+    timing_methods_table(lua, |method| {
+      seconds(analysis::state_helper::check_live_delta(
+        snapshot,
+        false,
+        last_comparison,
+        method,
+      ))
+    })?,
+  )?;
+  analysis_table.set(
+    "live_split_delta",
+    timing_methods_table(lua, |method| {
+      seconds(analysis::state_helper::check_live_delta(
+        snapshot,
+        true,
+        last_comparison,
+        method,
+      ))
+    })?,
+  )?;
+
+  if let Some(live) = live_segment {
+    analysis_table
+      .get::<LuaTable>("segments")?
+      .get::<LuaTable>(live + 1)?
+      .set(
+        "live_segment_time",
+        timing_methods_table(lua, |method| {
+          seconds(analysis::state_helper::live_segment_time(
+            snapshot, live, method,
+          ))
+        })?,
+      )?;
+  }
+
+  let pb_chance = analysis::pb_chance::for_timer(snapshot);
+  analysis_table.set(
+    "pb_chance",
+    live_table(lua, "chance", pb_chance.0, pb_chance.1)?,
+  )?;
+
+  analysis_table.set(
+    "total_playtime",
+    analysis::total_playtime::calculate(&timer).total_seconds(),
+  )?;
+
+  Ok(())
+}
+
+/// builds the analysis part of a timer cache, remembering the possible time saves the live values are derived from
+fn build_analysis(
+  lua: &Lua,
+  timer: &Timer,
+  snapshot: &Snapshot,
+  comparison_names: &[String],
+  split_key: &SplitKey,
+) -> Result<(LuaRegistryKey, Vec<f64>, Vec<Vec<f64>>)> {
+  let analysis_table = build_analysis_table(lua, timer, snapshot, comparison_names)?;
+
+  let mut live_possible_time_save = Vec::new();
+  let mut total_possible_time_saves = Vec::new();
+  for comp_name in comparison_names {
+    match split_key.live_segment(timer.run()) {
+      Some(live) => {
+        let pst = analysis::possible_time_save::calculate(snapshot, live, comp_name, true);
+        live_possible_time_save.push(seconds(pst.0).unwrap_or(0.));
+        total_possible_time_saves.push(
+          (0..=live)
+            .map(|i| {
+              analysis::possible_time_save::calculate_total(snapshot, i, comp_name)
+                .0
+                .total_seconds()
+            })
+            .collect(),
+        );
+      }
+      None => {
+        live_possible_time_save.push(0.);
+        total_possible_time_saves.push(Vec::new());
+      }
+    }
+  }
+
+  Ok((
+    lua.create_registry_value(analysis_table)?,
+    live_possible_time_save,
+    total_possible_time_saves,
+  ))
+}
+
+/// general value injector
+///
+/// every timer gets a `{snapshot, run, analysis}` entry in the `timers` global,
+/// and the first one is also published as the `snapshot`, `run` and `analysis` globals
+///
+/// tables are cached between calls, see [`TimerCache`]
+pub fn inject_values_in_lua(lua: &Lua, timers: &[(&Timer, &Repository)]) -> Result<()> {
+  if lua.app_data_ref::<TimerCaches>().is_none() {
+    lua.set_app_data(TimerCaches::default());
+  }
+
+  let timers_table = lua.create_table()?;
+  for (i, (timer, repository)) in timers.iter().enumerate() {
+    timers_table.push(timer_values(lua, i, timer, repository)?)?;
+  }
+
+  if let Some(first) = timers_table.get::<Option<LuaTable>>(1)? {
+    lua
+      .globals()
+      .set("snapshot", first.get::<LuaValue>("snapshot")?)?;
+    lua.globals().set("run", first.get::<LuaValue>("run")?)?;
+    lua
+      .globals()
+      .set("analysis", first.get::<LuaValue>("analysis")?)?;
+  }
+  lua.globals().set("timers", timers_table)?;
+
+  Ok(())
+}
+
+/// times of the first and previous frames
+struct FrameClock {
+  start: Instant,
+  last: Instant,
+}
+
+/// frame clock injector, to be called once per frame
+///
+/// the `frame` global holds the monotonic time since the first frame and the time since the previous one,
+/// in seconds, so components can animate independently from the timers
+pub fn inject_frame_in_lua(lua: &Lua) -> Result<()> {
+  let now = Instant::now();
+  if lua.app_data_ref::<FrameClock>().is_none() {
+    lua.set_app_data(FrameClock {
+      start: now,
+      last: now,
+    });
+  }
+
+  let (time, delta) = {
+    let mut clock = lua
+      .app_data_mut::<FrameClock>()
+      .ok_or(anyhow::Error::msg("couldn't get frame clock"))?;
+    let delta = now - clock.last;
+    clock.last = now;
+    (now - clock.start, delta)
+  };
+
+  let frame = lua.create_table()?;
+  frame.set("time", time.as_secs_f64())?;
+  frame.set("delta", delta.as_secs_f64())?;
+  lua.globals().set("frame", frame)?;
 
   Ok(())
 }
+
+/// builds the `{snapshot, run, analysis}` table of a single timer, updating its cache
+fn timer_values(
+  lua: &Lua,
+  index: usize,
+  timer: &Timer,
+  repository: &Repository,
+) -> Result<LuaTable> {
+  let run = timer.run();
+  let snapshot = timer.snapshot();
+  let run_key = run_key(run);
+  let split_key = SplitKey::new(run_key, timer);
+
+  let mut caches = lua
+    .app_data_mut::<TimerCaches>()
+    .ok_or(anyhow::Error::msg("couldn't get timer caches"))?;
+
+  let run_valid = caches
+    .0
+    .get(&index)
+    .map(|c| c.run_key == run_key)
+    .unwrap_or(false);
+  if !run_valid {
+    let mut comparison_names: Vec<String> = vec!["Personal Best".to_string()];
+    comparison_names.extend(run.comparisons().map(|f| f.to_string()));
+
+    let run_table = build_run_table(lua, run, repository, &comparison_names)?;
+    let (analysis, live_possible_time_save, total_possible_time_saves) =
+      build_analysis(lua, timer, &snapshot, &comparison_names, &split_key)?;
+    caches.0.insert(
+      index,
+      TimerCache {
+        run_key,
+        run: lua.create_registry_value(run_table)?,
+        comparison_names,
+        split_key,
+        analysis,
+        live_possible_time_save,
+        total_possible_time_saves,
+      },
+    );
+  } else if let Some(cache) = caches.0.get_mut(&index) {
+    if cache.split_key != split_key {
+      let (analysis, live_possible_time_save, total_possible_time_saves) =
+        build_analysis(lua, timer, &snapshot, &cache.comparison_names, &split_key)?;
+      cache.analysis = analysis;
+      cache.live_possible_time_save = live_possible_time_save;
+      cache.total_possible_time_saves = total_possible_time_saves;
+      cache.split_key = split_key;
+    }
+  }
+
+  let cache = caches
+    .0
+    .get(&index)
+    .ok_or(anyhow::Error::msg("couldn't get timer cache"))?;
+
+  let analysis_table = lua.registry_value::<LuaTable>(&cache.analysis)?;
+  refresh_live_values(lua, &analysis_table, cache, timer, &snapshot)?;
+
+  let values_table = lua.create_table()?;
+  values_table.set("snapshot", build_snapshot_table(lua, &snapshot)?)?;
+  values_table.set("run", lua.registry_value::<LuaTable>(&cache.run)?)?;
+  values_table.set("analysis", analysis_table)?;
+
+  Ok(values_table)
+}
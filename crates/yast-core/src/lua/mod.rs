@@ -3,8 +3,11 @@
 use anyhow::Result;
 use mlua::prelude::*;
 
+pub mod budget;
 pub mod inject;
+pub mod sandbox;
 pub mod settings;
+pub mod tween;
 pub mod widgets;
 
 /// context used by all apps
@@ -13,8 +16,12 @@ pub struct LuaContext {
 }
 
 impl LuaContext {
+  /// creates the lua state, without the `io` and `os` libraries
   pub fn init() -> Result<Self> {
-    let lua = Lua::new();
+    let lua = Lua::new_with(
+      LuaStdLib::ALL_SAFE ^ LuaStdLib::IO ^ LuaStdLib::OS,
+      LuaOptions::default(),
+    )?;
 
     let package: LuaTable = lua.globals().get("package")?;
     let mut current_path: String = package.get("path")?;
@@ -30,6 +37,8 @@ impl LuaContext {
 
     settings::component_settings(&lua)?;
     widgets::widgets(&lua)?;
+    tween::init_tween(&lua)?;
+    sandbox::init_api(&lua)?;
 
     Ok(Self { lua })
   }
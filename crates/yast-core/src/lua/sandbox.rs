@@ -0,0 +1,383 @@
+//! per-component lua environments
+//!
+//! every component instance gets its own environment table, falling back to a read-only view of the globals.
+//! globals set by a component, and values injected while building it, stay in its environment.
+//! dangerous globals are hidden from components, which have to declare the capabilities they need instead.
+//! lua comparisons run in the same kind of environment
+
+use std::{
+  fs,
+  path::{Component as PathComponent, Path, PathBuf},
+};
+
+use anyhow::Result;
+use mlua::prelude::*;
+
+use crate::{lua::budget::protected_calls, paths::sanitize};
+
+/// registry key of the read-only view of the globals
+const API_KEY: &str = "yast_api";
+/// registry key of the functions given by the `time` capability
+const TIME_KEY: &str = "yast_time";
+/// registry key of the function wrapping tables in read-only proxies
+const READ_ONLY_KEY: &str = "yast_read_only";
+/// registry key of the `next` functions of the proxies, by proxy
+const PROXIES_KEY: &str = "yast_proxies";
+
+/// functions of the `os` library given by the `time` capability
+const TIME_FUNCTIONS: [&str; 4] = ["time", "clock", "date", "difftime"];
+
+/// globals components can't access, as they give access to the system or escape the sandbox
+///
+/// the `io` and `os` libraries aren't loaded at all, see [`LuaContext::init`]
+///
+/// [`LuaContext::init`]: crate::lua::LuaContext::init
+const HIDDEN_GLOBALS: [&str; 8] = [
+  "debug",
+  "package",
+  "require",
+  "dofile",
+  "loadfile",
+  "load",
+  "rawset",
+  "collectgarbage",
+];
+
+/// capabilities components can declare in their `capabilities` field, with their description
+pub const CAPABILITIES: [(&str, &str); 2] = [
+  (
+    "time",
+    "read the current date and time (`os.time`, `os.clock`, `os.date`, `os.difftime`)",
+  ),
+  ("files", "read files in its own folder (`files.read`)"),
+];
+
+/// description of a capability, `None` if it doesn't exist
+pub fn capability_description(capability: &str) -> Option<&'static str> {
+  CAPABILITIES
+    .iter()
+    .find(|(c, _)| *c == capability)
+    .map(|(_, d)| *d)
+}
+
+/// folder a component can read files from with the `files` capability
+pub fn component_folder(name: &str) -> Result<PathBuf> {
+  script_folder("yast/components", name)
+}
+
+/// folder a lua comparison can read files from with the `files` capability
+pub fn comparison_folder(name: &str) -> Result<PathBuf> {
+  script_folder("yast/comparisons", name)
+}
+
+fn script_folder(directory: &str, name: &str) -> Result<PathBuf> {
+  let mut path = dirs::data_dir().ok_or(anyhow::Error::msg("couldn't get data directory"))?;
+  path.push(directory);
+  path.push(sanitize(name));
+  Ok(path)
+}
+
+/// per-environment `require`, loading modules in the environment they're required from
+///
+/// modules are cached per environment, as their functions read the globals of the component using them
+const REQUIRE: &str = r#"
+local env = ...
+local loaded = {}
+return function(name)
+  if loaded[name] == nil then
+    local file, err = package.searchpath(name, package.path)
+    if file == nil then
+      error("module '" .. name .. "' not found:" .. err, 2)
+    end
+    local chunk = assert(loadfile(file, "t", env))
+    local value = chunk(name, file)
+    if value == nil then
+      value = true
+    end
+    loaded[name] = value
+  end
+  return loaded[name]
+end
+"#;
+
+/// creates the function wrapping a table in a read-only proxy
+///
+/// nested tables are wrapped too, and a table always gets the same proxy, which lives as long as the table
+const READ_ONLY: &str = r#"
+local read_only_error, proxy_nexts = ...
+local targets = setmetatable({}, { __mode = "k" })
+local proxies = setmetatable({}, { __mode = "k" })
+local next, type = next, type
+
+local read_only
+local function wrap(value)
+  if type(value) == "table" then
+    return read_only(value)
+  end
+  return value
+end
+
+local function proxy_next(proxy, key)
+  local next_key, value = next(targets[proxy], key)
+  if next_key == nil then
+    return nil
+  end
+  return next_key, wrap(value)
+end
+
+local metatable = {
+  __index = function(proxy, key)
+    return wrap(targets[proxy][key])
+  end,
+  __newindex = read_only_error,
+  __len = function(proxy)
+    return #targets[proxy]
+  end,
+  __pairs = function(proxy)
+    return proxy_next, proxy, nil
+  end,
+  __metatable = false,
+}
+
+read_only = function(target)
+  local proxy = proxies[target]
+  if proxy == nil then
+    proxy = setmetatable({}, metatable)
+    targets[proxy] = target
+    proxies[target] = proxy
+    proxy_nexts[proxy] = proxy_next
+  end
+  return proxy
+end
+return read_only
+"#;
+
+/// creates `next`, `rawget` and `rawlen`, going through the proxies instead of seeing them as empty tables
+const PROXY_AWARE: &str = r#"
+local proxy_nexts = ...
+local next, rawget, rawlen = next, rawget, rawlen
+return {
+  next = function(t, key)
+    local proxy_next = proxy_nexts[t]
+    if proxy_next ~= nil then
+      return proxy_next(t, key)
+    end
+    return next(t, key)
+  end,
+  rawget = function(t, key)
+    if proxy_nexts[t] ~= nil then
+      return t[key]
+    end
+    return rawget(t, key)
+  end,
+  rawlen = function(t)
+    if proxy_nexts[t] ~= nil then
+      return #t
+    end
+    return rawlen(t)
+  end,
+}
+"#;
+
+pub(crate) fn read_only_error(lua: &Lua) -> LuaResult<LuaFunction> {
+  lua.create_function(|_, _: LuaMultiValue| -> LuaResult<()> {
+    Err(LuaError::runtime(
+      "the yast api is read-only, set a local or a global of your component instead",
+    ))
+  })
+}
+
+/// registers a proxy, so `next`, `rawget` and `rawlen` go through it
+///
+/// `next` is the function iterating over the proxy, as `next(proxy, key)` would over the wrapped table
+pub(crate) fn register_proxy(lua: &Lua, proxy: &LuaTable, next: LuaFunction) -> LuaResult<()> {
+  lua
+    .named_registry_value::<LuaTable>(PROXIES_KEY)?
+    .raw_set(proxy, next)
+}
+
+/// wraps a table, and the tables it holds, in proxies refusing writes
+pub(crate) fn read_only(lua: &Lua, table: LuaTable) -> LuaResult<LuaTable> {
+  lua
+    .named_registry_value::<LuaFunction>(READ_ONLY_KEY)?
+    .call(table)
+}
+
+/// creates the read-only view of the globals shared by every component environment
+///
+/// tables are wrapped in read-only proxies, so components can't modify the api tables (`widgets`, `string`...).
+/// `next`, `rawget` and `rawlen` are replaced by versions going through the proxies,
+/// and `pcall`, `xpcall` and `coroutine.resume` by versions that can't catch an exceeded time budget
+///
+/// the `os` library is loaded only to keep the functions of the `time` capability, and removed from the globals
+pub fn init_api(lua: &Lua) -> LuaResult<()> {
+  let globals = lua.globals();
+
+  lua.load_std_libs(LuaStdLib::OS)?;
+  let os = globals.raw_get::<LuaTable>("os")?;
+  let time = lua.create_table()?;
+  for function in TIME_FUNCTIONS {
+    time.raw_set(function, os.raw_get::<LuaFunction>(function)?)?;
+  }
+  lua.set_named_registry_value(TIME_KEY, time)?;
+  globals.raw_set("os", LuaNil)?;
+  globals
+    .raw_get::<LuaTable>("package")?
+    .raw_get::<LuaTable>("loaded")?
+    .raw_set("os", LuaNil)?;
+
+  // `next` functions by proxy, dropped along with the proxies
+  let proxy_nexts = lua.create_table()?;
+  let proxy_nexts_metatable = lua.create_table()?;
+  proxy_nexts_metatable.raw_set("__mode", "k")?;
+  proxy_nexts.set_metatable(Some(proxy_nexts_metatable))?;
+  lua.set_named_registry_value(PROXIES_KEY, proxy_nexts.clone())?;
+
+  let read_only_function = lua
+    .load(READ_ONLY)
+    .set_name("read_only")
+    .call::<LuaFunction>((read_only_error(lua)?, proxy_nexts.clone()))?;
+  lua.set_named_registry_value(READ_ONLY_KEY, read_only_function.clone())?;
+
+  // globals replaced in the api: proxy-aware raw accesses, and protected calls respecting the time budget
+  let overrides = lua
+    .load(PROXY_AWARE)
+    .set_name("proxy_aware")
+    .call::<LuaTable>(proxy_nexts)?;
+  let protected_calls = protected_calls(lua)?;
+  overrides.raw_set("pcall", protected_calls.raw_get::<LuaFunction>("pcall")?)?;
+  overrides.raw_set("xpcall", protected_calls.raw_get::<LuaFunction>("xpcall")?)?;
+  let coroutine = lua.create_table()?;
+  for pair in globals
+    .raw_get::<LuaTable>("coroutine")?
+    .pairs::<LuaValue, LuaValue>()
+  {
+    let (key, value) = pair?;
+    coroutine.raw_set(key, value)?;
+  }
+  coroutine.raw_set("resume", protected_calls.raw_get::<LuaFunction>("resume")?)?;
+  overrides.raw_set("coroutine", coroutine)?;
+
+  let api = lua.create_table()?;
+  let metatable = lua.create_table()?;
+  metatable.raw_set(
+    "__index",
+    lua.create_function(move |_, (_, key): (LuaValue, LuaValue)| {
+      if let LuaValue::String(name) = &key {
+        if let Ok(name) = name.to_str() {
+          if HIDDEN_GLOBALS.contains(&&*name) {
+            return Ok(LuaNil);
+          }
+        }
+        match overrides.raw_get::<LuaValue>(name.clone())? {
+          LuaValue::Nil => {}
+          LuaValue::Table(t) => return read_only_function.call(t),
+          value => return Ok(value),
+        }
+      }
+      match globals.raw_get::<LuaValue>(key)? {
+        LuaValue::Table(t) => read_only_function.call(t),
+        value => Ok(value),
+      }
+    })?,
+  )?;
+  metatable.raw_set("__newindex", read_only_error(lua)?)?;
+  // the api is shared by every component, so its metatable can't be read or replaced
+  metatable.raw_set("__metatable", false)?;
+  api.set_metatable(Some(metatable))?;
+
+  lua.set_named_registry_value(API_KEY, api)?;
+
+  // the string metatable would otherwise give access to the shared `string` table
+  lua.load("getmetatable('').__metatable = false").exec()
+}
+
+/// creates a sandboxed environment, with a read-only view of the globals and its own `require`
+pub fn environment(lua: &Lua) -> LuaResult<LuaTable> {
+  let api = lua.named_registry_value::<LuaTable>(API_KEY)?;
+
+  let env = lua.create_table()?;
+  let metatable = lua.create_table()?;
+  metatable.raw_set("__index", api.clone())?;
+  metatable.raw_set("__metatable", false)?;
+  env.set_metatable(Some(metatable))?;
+
+  env.raw_set("_G", env.clone())?;
+  env.raw_set(
+    "require",
+    lua
+      .load(REQUIRE)
+      .set_name("require")
+      .call::<LuaFunction>(env.clone())?,
+  )?;
+
+  Ok(env)
+}
+
+/// creates the environment of a component instance
+pub fn component_environment(lua: &Lua) -> LuaResult<LuaTable> {
+  let env = environment(lua)?;
+
+  // the children table gets the number of children of the instance
+  let children = lua.create_table()?;
+  let api_children = lua.globals().raw_get::<LuaTable>("children")?;
+  for pair in api_children.pairs::<LuaValue, LuaValue>() {
+    let (key, value) = pair?;
+    children.raw_set(key, value)?;
+  }
+  env.raw_set("children", children)?;
+
+  Ok(env)
+}
+
+/// gives an environment the globals of the capabilities declared by its script
+///
+/// `folder` is the folder the `files` capability gives access to
+pub fn grant_capabilities(
+  lua: &Lua,
+  env: &LuaTable,
+  name: &str,
+  folder: PathBuf,
+  capabilities: &[String],
+) -> Result<()> {
+  for capability in capabilities {
+    match capability.as_str() {
+      "time" => {
+        let functions = lua.named_registry_value::<LuaTable>(TIME_KEY)?;
+        let time = lua.create_table()?;
+        for function in TIME_FUNCTIONS {
+          time.raw_set(function, functions.raw_get::<LuaFunction>(function)?)?;
+        }
+        env.raw_set("os", time)?;
+      }
+      "files" => {
+        let folder = folder.clone();
+        let files = lua.create_table()?;
+        files.raw_set(
+          "read",
+          lua.create_function(move |_, path: String| {
+            let relative = Path::new(&path);
+            if !relative
+              .components()
+              .all(|c| matches!(c, PathComponent::Normal(_)))
+            {
+              return Err(LuaError::runtime(format!(
+                "can't read {}: only relative paths inside the component's folder are allowed",
+                path
+              )));
+            }
+            fs::read_to_string(folder.join(relative)).map_err(LuaError::external)
+          })?,
+        )?;
+        env.raw_set("files", files)?;
+      }
+      other => {
+        return Err(anyhow::Error::msg(format!(
+          "{} requires unknown capability {}",
+          name, other
+        )));
+      }
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,157 @@
+//! easing and interpolation helpers, available as the `tween` global
+//!
+//! animations are driven by the components themselves: they compute a progress from `frame.time`,
+//! usually with a start time kept in their `state`, and interpolate between two values with it
+
+use std::f32::consts::PI;
+
+use iced::Color;
+use mlua::prelude::*;
+
+/// easing functions, by name
+pub const EASINGS: [&str; 14] = [
+  "linear",
+  "in_quad",
+  "out_quad",
+  "in_out_quad",
+  "in_cubic",
+  "out_cubic",
+  "in_out_cubic",
+  "in_sine",
+  "out_sine",
+  "in_out_sine",
+  "in_expo",
+  "out_expo",
+  "out_back",
+  "out_bounce",
+];
+
+/// eases a progress, clamped between 0 and 1
+pub fn ease(easing: &str, t: f32) -> LuaResult<f32> {
+  let t = t.clamp(0., 1.);
+  Ok(match easing {
+    "linear" => t,
+    "in_quad" => t * t,
+    "out_quad" => 1. - (1. - t) * (1. - t),
+    "in_out_quad" => {
+      if t < 0.5 {
+        2. * t * t
+      } else {
+        1. - (-2. * t + 2.).powi(2) / 2.
+      }
+    }
+    "in_cubic" => t * t * t,
+    "out_cubic" => 1. - (1. - t).powi(3),
+    "in_out_cubic" => {
+      if t < 0.5 {
+        4. * t * t * t
+      } else {
+        1. - (-2. * t + 2.).powi(3) / 2.
+      }
+    }
+    "in_sine" => 1. - (t * PI / 2.).cos(),
+    "out_sine" => (t * PI / 2.).sin(),
+    "in_out_sine" => -((t * PI).cos() - 1.) / 2.,
+    "in_expo" => {
+      if t == 0. {
+        0.
+      } else {
+        2f32.powf(10. * t - 10.)
+      }
+    }
+    "out_expo" => {
+      if t == 1. {
+        1.
+      } else {
+        1. - 2f32.powf(-10. * t)
+      }
+    }
+    "out_back" => {
+      let c1 = 1.70158;
+      let c3 = c1 + 1.;
+      1. + c3 * (t - 1.).powi(3) + c1 * (t - 1.).powi(2)
+    }
+    "out_bounce" => {
+      let n1 = 7.5625;
+      let d1 = 2.75;
+      if t < 1. / d1 {
+        n1 * t * t
+      } else if t < 2. / d1 {
+        let t = t - 1.5 / d1;
+        n1 * t * t + 0.75
+      } else if t < 2.5 / d1 {
+        let t = t - 2.25 / d1;
+        n1 * t * t + 0.9375
+      } else {
+        let t = t - 2.625 / d1;
+        n1 * t * t + 0.984375
+      }
+    }
+    other => {
+      return Err(LuaError::runtime(format!(
+        "unknown easing {}, expected one of {}",
+        other,
+        EASINGS.join(", ")
+      )));
+    }
+  })
+}
+
+/// interpolates between two numbers, `easing` being linear if not given
+pub fn lerp(from: f32, to: f32, t: f32, easing: Option<&str>) -> LuaResult<f32> {
+  Ok(from + (to - from) * ease(easing.unwrap_or("linear"), t)?)
+}
+
+/// interpolates between two `{r, g, b, a}` colors, component by component
+pub fn lerp_color(from: [f32; 4], to: [f32; 4], t: f32, easing: Option<&str>) -> LuaResult<Color> {
+  let t = ease(easing.unwrap_or("linear"), t)?;
+  let channel = |i: usize| from[i] + (to[i] - from[i]) * t;
+  Ok(Color::from_rgba(
+    channel(0),
+    channel(1),
+    channel(2),
+    channel(3),
+  ))
+}
+
+/// `tween` global injector
+pub fn init_tween(lua: &Lua) -> LuaResult<()> {
+  let tween = lua.create_table()?;
+
+  tween.set(
+    "ease",
+    lua.create_function(|_, (t, easing): (f32, Option<String>)| {
+      ease(easing.as_deref().unwrap_or("linear"), t)
+    })?,
+  )?;
+  tween.set(
+    "lerp",
+    lua.create_function(
+      |_, (from, to, t, easing): (f32, f32, f32, Option<String>)| {
+        lerp(from, to, t, easing.as_deref())
+      },
+    )?,
+  )?;
+  tween.set(
+    "color",
+    lua.create_function(
+      |_, (from, to, t, easing): ([f32; 4], [f32; 4], f32, Option<String>)| {
+        let c = lerp_color(from, to, t, easing.as_deref())?;
+        Ok([c.r, c.g, c.b, c.a])
+      },
+    )?,
+  )?;
+  tween.set(
+    "progress",
+    lua.create_function(|_, (start, duration, now): (f64, f64, f64)| {
+      if duration <= 0. {
+        return Ok(1.);
+      }
+      Ok(((now - start) / duration).clamp(0., 1.))
+    })?,
+  )?;
+  tween.set("easings", EASINGS.to_vec())?;
+
+  lua.globals().set("tween", tween)?;
+  Ok(())
+}
@@ -8,7 +8,7 @@ use mlua::prelude::*;
 
 use crate::{
   layout::{component::Component, settings::LayoutSettings},
-  lua::widgets::LuaWidget,
+  lua::{tween::lerp_color, widgets::LuaWidget},
   repository::Repository,
 };
 
@@ -219,6 +219,18 @@ impl LuaUserData for LuaWidgetContainer {
       },
     );
 
+    methods.add_method(
+      "transition",
+      |_, w, (from, to, t, easing): ([f32; 4], [f32; 4], f32, Option<String>)| {
+        let mut style = w.style.unwrap_or_default();
+        style.background = Some(lerp_color(from, to, t, easing.as_deref())?.into());
+        Ok(LuaWidgetContainer {
+          style: Some(style),
+          ..w.clone()
+        })
+      },
+    );
+
     methods.add_method("align_y", |_, w, s: String| match s.as_str() {
       "top" => Ok(LuaWidgetContainer {
         align_y: Some(Vertical::Top),
@@ -2,7 +2,7 @@ use anyhow::Result;
 use iced::{ContentFit, Element, Length, Rectangle, widget::image};
 use mlua::prelude::*;
 
-use crate::lua::widgets::LuaWidget;
+use crate::lua::{tween::lerp, widgets::LuaWidget};
 
 #[derive(Clone)]
 pub struct ImageHandleLua(pub image::Handle);
@@ -186,6 +186,16 @@ impl LuaUserData for LuaWidgetImage {
       })
     });
 
+    methods.add_method(
+      "transition",
+      |_, w, (from, to, t, easing): (f32, f32, f32, Option<String>)| {
+        Ok(LuaWidgetImage {
+          opacity: Some(lerp(from, to, t, easing.as_deref())?),
+          ..w.clone()
+        })
+      },
+    );
+
     methods.add_method(
       "crop",
       |_, w, (x, y, width, height): (u32, u32, u32, u32)| {
@@ -52,7 +52,10 @@ impl FromLua for LuaWidget {
   fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
     match value {
       LuaValue::UserData(ud) => Ok(ud.borrow::<Self>()?.clone()),
-      _ => unreachable!(),
+      other => Err(LuaError::runtime(format!(
+        "expected a widget, got {}",
+        other.type_name()
+      ))),
     }
   }
 }
@@ -79,7 +82,8 @@ impl LuaWidget {
         let mut new_path = path.clone();
         new_path.push(index);
 
-        child.build(lua, new_path, layout_settings, repository)
+        // children failing are replaced by their own placeholder instead of failing their parent
+        Ok(child.build(lua, new_path, layout_settings, repository))
       }
       LuaWidget::Column(inner) => inner.build(tree, lua, path, layout_settings, repository),
       LuaWidget::Row(inner) => inner.build(tree, lua, path, layout_settings, repository),
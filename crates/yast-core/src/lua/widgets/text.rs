@@ -6,7 +6,7 @@ use iced::{
 };
 use mlua::prelude::*;
 
-use crate::lua::widgets::LuaWidget;
+use crate::lua::{tween::lerp_color, widgets::LuaWidget};
 
 #[derive(Clone)]
 pub struct LuaWidgetText {
@@ -119,6 +119,18 @@ impl LuaUserData for LuaWidgetText {
       })
     });
 
+    methods.add_method(
+      "transition",
+      |_, w, (from, to, t, easing): ([f32; 4], [f32; 4], f32, Option<String>)| {
+        Ok(LuaWidgetText {
+          style: Some(text::Style {
+            color: Some(lerp_color(from, to, t, easing.as_deref())?),
+          }),
+          ..w.clone()
+        })
+      },
+    );
+
     methods.add_method(
       "width",
       |_, w, (typ, unit): (String, Option<f32>)| match typ.as_str() {
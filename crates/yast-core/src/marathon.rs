@@ -0,0 +1,199 @@
+//! marathons: several splits files chained into one aggregated run
+
+use std::{
+  fs::{self, File},
+  io::BufWriter,
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use livesplit_core::{
+  Run, Time, TimeSpan, Timer, TimerPhase,
+  run::{
+    parser,
+    saver::livesplit::{IoWrite, save_run},
+  },
+};
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// marathon definition, stored as a `.yasm` file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarathonDefinition {
+  pub name: String,
+  /// splits files, relative to the definition file
+  pub splits: Vec<String>,
+}
+
+/// marathon being run
+pub struct Marathon {
+  pub name: String,
+  paths: Vec<PathBuf>,
+  pub runs: Vec<Run>,
+  /// index of the category being run
+  pub current: usize,
+  /// final times of the categories already done in the current attempt
+  pub completed: Vec<Time>,
+}
+
+fn add(a: Option<TimeSpan>, b: Option<TimeSpan>) -> Option<TimeSpan> {
+  Some(a? + b?)
+}
+
+fn add_times(a: Time, b: Time) -> Time {
+  Time::new()
+    .with_real_time(add(a.real_time, b.real_time))
+    .with_game_time(add(a.game_time, b.game_time))
+}
+
+impl Marathon {
+  pub fn load(path: &Path) -> Result<Self> {
+    let definition = ron::from_str::<MarathonDefinition>(&fs::read_to_string(path)?)?;
+    if definition.splits.is_empty() {
+      return Err(anyhow::Error::msg("marathon doesn't contain any splits"));
+    }
+
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut paths = Vec::new();
+    let mut runs = Vec::new();
+    for splits in &definition.splits {
+      let p = directory.join(splits);
+      let source = fs::read(&p)?;
+      runs.push(parser::parse_and_fix(&source, Some(&p))?.run);
+      paths.push(p);
+    }
+
+    Ok(Self {
+      name: definition.name,
+      paths,
+      runs,
+      current: 0,
+      completed: Vec::new(),
+    })
+  }
+
+  /// moves to the next category when the current one is finished, and back to the first one on reset
+  ///
+  /// returns whether the timer's run was replaced
+  pub fn update(&mut self, timer: &mut Timer) -> Result<bool> {
+    match timer.current_phase() {
+      TimerPhase::Ended if self.current + 1 < self.runs.len() => {
+        let final_time = timer
+          .run()
+          .segments()
+          .last()
+          .map(|s| s.split_time())
+          .unwrap_or_default();
+        timer.reset(true);
+        self.runs[self.current] = timer.run().clone();
+        self.completed.push(final_time);
+        self.current += 1;
+        timer
+          .set_run(self.runs[self.current].clone())
+          .map_err(|_| anyhow::Error::msg("couldn't load the next marathon category"))?;
+        timer.start();
+        Ok(true)
+      }
+      TimerPhase::NotRunning if self.current > 0 => {
+        self.runs[self.current] = timer.run().clone();
+        self.current = 0;
+        self.completed.clear();
+        timer
+          .set_run(self.runs[0].clone())
+          .map_err(|_| anyhow::Error::msg("couldn't load the first marathon category"))?;
+        Ok(true)
+      }
+      _ => Ok(false),
+    }
+  }
+
+  /// splits file of the category being run
+  pub fn current_path(&self) -> &Path {
+    &self.paths[self.current]
+  }
+
+  /// total time of the marathon attempt, categories done included
+  pub fn total_time(&self, timer: &Timer) -> Time {
+    self
+      .completed
+      .iter()
+      .fold(timer.snapshot().current_time(), |total, t| {
+        add_times(total, *t)
+      })
+  }
+
+  /// sum of the personal bests of every category
+  pub fn personal_best(&self) -> Time {
+    self.runs.iter().fold(Time::zero(), |total, run| {
+      add_times(
+        total,
+        run
+          .segments()
+          .last()
+          .map(|s| s.personal_best_split_time())
+          .unwrap_or_default(),
+      )
+    })
+  }
+
+  /// saves every category back in its splits file
+  pub fn save_all(&mut self, current: &Run) -> Result<()> {
+    self.runs[self.current] = current.clone();
+    for (run, path) in self.runs.iter().zip(&self.paths) {
+      let writer = BufWriter::new(File::create(path)?);
+      save_run(run, IoWrite(writer))?;
+    }
+    Ok(())
+  }
+}
+
+fn time_table(lua: &Lua, time: Time) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  table.set("real_time", time.real_time.map(|t| t.total_seconds()))?;
+  table.set("game_time", time.game_time.map(|t| t.total_seconds()))?;
+  Ok(table)
+}
+
+/// sets the `marathon` global, which is nil when no marathon is loaded
+pub fn inject_marathon_in_lua(lua: &Lua, marathon: Option<&Marathon>, timer: &Timer) -> Result<()> {
+  let Some(marathon) = marathon else {
+    lua.globals().set("marathon", LuaNil)?;
+    return Ok(());
+  };
+
+  let marathon_table = lua.create_table()?;
+  marathon_table.set("name", marathon.name.as_str())?;
+  marathon_table.set("current_category", marathon.current + 1)?;
+  marathon_table.set("category_count", marathon.runs.len())?;
+  marathon_table.set("total_time", time_table(lua, marathon.total_time(timer))?)?;
+  marathon_table.set("personal_best", time_table(lua, marathon.personal_best())?)?;
+
+  let categories_table = lua.create_table()?;
+  for (i, run) in marathon.runs.iter().enumerate() {
+    let category_table = lua.create_table()?;
+    category_table.set("game_name", run.game_name())?;
+    category_table.set("category_name", run.category_name())?;
+    category_table.set(
+      "personal_best",
+      time_table(
+        lua,
+        run
+          .segments()
+          .last()
+          .map(|s| s.personal_best_split_time())
+          .unwrap_or_default(),
+      )?,
+    )?;
+    let final_time = match marathon.completed.get(i) {
+      Some(t) => Some(time_table(lua, *t)?),
+      None => None,
+    };
+    category_table.set("final_time", final_time)?;
+    categories_table.push(category_table)?;
+  }
+  marathon_table.set("categories", categories_table)?;
+
+  lua.globals().set("marathon", marathon_table)?;
+
+  Ok(())
+}
@@ -0,0 +1,19 @@
+//! paths of the files yast stores, in the data directory or next to the splits
+
+use std::path::{Path, PathBuf};
+
+/// replaces every character that isn't alphanumeric, so names can be used as file names
+pub(crate) fn sanitize(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+    .collect()
+}
+
+/// path of a file holding yast data about the attempts of a splits file, stored next to it
+///
+/// attempt indices only mean something in their splits file, so the data follows the file
+/// instead of the game and category names, e.g. `run.lss` gets `run.<kind>.ron`
+pub(crate) fn splits_data_path(splits: &Path, kind: &str) -> PathBuf {
+  splits.with_extension(format!("{}.ron", kind))
+}
@@ -0,0 +1,337 @@
+//! segment practice: timing a range of segments without touching the run's history
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use livesplit_core::{Run, Segment, SharedTimer, Time, TimeSpan, Timer, TimerPhase, TimingMethod};
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  layout::HotkeyAction, paths::splits_data_path, repository::Repository,
+  statistics::SegmentStatistics,
+};
+
+/// segment or split time, for both timing methods
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PracticeTime {
+  pub real_time: Option<f64>,
+  pub game_time: Option<f64>,
+}
+
+impl PracticeTime {
+  fn from_time(time: Time) -> Self {
+    Self {
+      real_time: time.real_time.map(|t| t.total_seconds()),
+      game_time: time.game_time.map(|t| t.total_seconds()),
+    }
+  }
+
+  pub fn get(&self, timing_method: TimingMethod) -> Option<f64> {
+    match timing_method {
+      TimingMethod::RealTime => self.real_time,
+      TimingMethod::GameTime => self.game_time,
+    }
+  }
+}
+
+/// single practice attempt
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PracticeAttempt {
+  /// index of the first practiced segment in the run
+  pub start: usize,
+  /// index of the last practiced segment in the run
+  pub end: usize,
+  /// unix timestamp of the end of the attempt
+  pub ended: u64,
+  /// times of the completed segments, starting at `start`
+  pub segments: Vec<PracticeTime>,
+  /// final time, only present if the whole range was completed
+  pub time: Option<PracticeTime>,
+}
+
+/// practice attempts of a run, stored next to its splits file (`<splits>.practice.ron`) instead of in it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PracticeHistory {
+  pub attempts: Vec<PracticeAttempt>,
+}
+
+impl PracticeHistory {
+  /// loads the practice history of a splits file, or an empty one if there's none yet or no splits file
+  pub fn load(splits: Option<&Path>) -> Result<Self> {
+    let Some(splits) = splits else {
+      return Ok(Self::default());
+    };
+    let path = splits_data_path(splits, "practice");
+    if !path.try_exists()? {
+      return Ok(Self::default());
+    }
+    Ok(ron::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  pub fn save(&self, splits: &Path) -> Result<()> {
+    fs::write(splits_data_path(splits, "practice"), ron::to_string(self)?)?;
+    Ok(())
+  }
+}
+
+fn difference(a: Option<TimeSpan>, b: Option<TimeSpan>) -> Option<TimeSpan> {
+  Some(a? - b?)
+}
+
+/// creates a run containing only the segments from `start` to `end` (both included)
+///
+/// personal best split times are shifted so that they start at zero, and the history is left out.
+/// icons are kept, so the practice run can be displayed like the run
+pub fn practice_run(run: &Run, start: usize, end: usize) -> Result<Run> {
+  if start > end || end >= run.len() {
+    return Err(anyhow::Error::msg("invalid practice range"));
+  }
+
+  let offset = match start {
+    0 => Time::zero(),
+    _ => run.segment(start - 1).personal_best_split_time(),
+  };
+
+  let mut practice = Run::new();
+  practice.set_game_name(run.game_name());
+  practice.set_category_name(run.category_name());
+  practice.set_game_icon(run.game_icon().clone());
+  for segment in &run.segments()[start..=end] {
+    let mut practice_segment = Segment::new(segment.name());
+    practice_segment.set_icon(segment.icon().clone());
+    practice_segment.set_best_segment_time(segment.best_segment_time());
+    let pb = segment.personal_best_split_time();
+    practice_segment.set_personal_best_split_time(
+      Time::new()
+        .with_real_time(difference(pb.real_time, offset.real_time))
+        .with_game_time(difference(pb.game_time, offset.game_time)),
+    );
+    practice.push_segment(practice_segment);
+  }
+
+  Ok(practice)
+}
+
+/// practice session over a range of segments
+pub struct Practice {
+  pub start: usize,
+  pub end: usize,
+  pub timer: SharedTimer,
+  /// icons of the practiced segments, matching the practice timer's segments
+  pub repository: Repository,
+  pub history: PracticeHistory,
+  /// splits file the practice history is saved next to, `None` if the run wasn't loaded from a file
+  splits: Option<PathBuf>,
+}
+
+impl Practice {
+  pub fn new(
+    run: &Run,
+    splits: Option<PathBuf>,
+    start: usize,
+    end: usize,
+    timing_method: TimingMethod,
+  ) -> Result<Self> {
+    let mut timer = Timer::new(practice_run(run, start, end)?)?;
+    timer.set_current_timing_method(timing_method);
+    let mut repository = Repository::default();
+    repository.update_from_splits(timer.run())?;
+
+    Ok(Self {
+      start,
+      end,
+      timer: timer.into_shared(),
+      repository,
+      history: PracticeHistory::load(splits.as_deref())?,
+      splits,
+    })
+  }
+
+  /// saves the current attempt in the practice history, then resets the practice timer
+  ///
+  /// attempts reset in their first segment are saved too, without segment times.
+  /// the practice run is thrown away when the session ends, so its own history is never updated,
+  /// and the history of runs without a splits file is only kept until the session ends
+  pub fn reset(&mut self) -> Result<()> {
+    let attempt = {
+      let mut timer = self
+        .timer
+        .write()
+        .map_err(|_| anyhow::Error::msg("couldn't access practice timer"))?;
+      if timer.current_phase() == TimerPhase::NotRunning {
+        return Ok(());
+      }
+
+      let completed = timer.current_split_index().unwrap_or(0);
+      let mut segments = Vec::new();
+      let mut previous = Time::zero();
+      for segment in timer.run().segments().iter().take(completed) {
+        let split = segment.split_time();
+        segments.push(PracticeTime::from_time(
+          Time::new()
+            .with_real_time(difference(split.real_time, previous.real_time))
+            .with_game_time(difference(split.game_time, previous.game_time)),
+        ));
+        previous = split;
+      }
+
+      let time = match timer.current_phase() {
+        TimerPhase::Ended => timer
+          .run()
+          .segments()
+          .last()
+          .map(|s| PracticeTime::from_time(s.split_time())),
+        _ => None,
+      };
+
+      timer.reset(false);
+
+      PracticeAttempt {
+        start: self.start,
+        end: self.end,
+        ended: SystemTime::now()
+          .duration_since(UNIX_EPOCH)
+          .map(|d| d.as_secs())
+          .unwrap_or_default(),
+        segments,
+        time,
+      }
+    };
+
+    self.history.attempts.push(attempt);
+    if let Some(splits) = &self.splits {
+      self.history.save(splits)?;
+    }
+
+    Ok(())
+  }
+
+  /// applies a hotkey action to the practice timer
+  ///
+  /// resets record the attempt, and starting again after finishing the range starts a new attempt
+  pub fn handle_hotkey(&mut self, action: HotkeyAction) -> Result<()> {
+    let phase = self
+      .timer
+      .read()
+      .map_err(|_| anyhow::Error::msg("couldn't access practice timer"))?
+      .current_phase();
+
+    match action {
+      HotkeyAction::ResetTimer => return self.reset(),
+      _ if action.reset_reason().is_some() => return self.reset(),
+      HotkeyAction::StartOrSplitTimer | HotkeyAction::StartTimer if phase == TimerPhase::Ended => {
+        self.reset()?;
+      }
+      _ => {}
+    }
+
+    let mut timer = self
+      .timer
+      .write()
+      .map_err(|_| anyhow::Error::msg("couldn't access practice timer"))?;
+    match action {
+      HotkeyAction::ResetTimer => {}
+      action => action.apply(&mut timer),
+    }
+
+    Ok(())
+  }
+
+  /// attempts over the currently practiced range
+  pub fn range_attempts(&self) -> impl Iterator<Item = &PracticeAttempt> {
+    self
+      .history
+      .attempts
+      .iter()
+      .filter(|a| a.start == self.start && a.end == self.end)
+  }
+
+  /// statistics of a run segment, from every practice attempt that went through it
+  pub fn segment_statistics(
+    &self,
+    segment: usize,
+    timing_method: TimingMethod,
+  ) -> SegmentStatistics {
+    let mut times = self
+      .history
+      .attempts
+      .iter()
+      .filter_map(|a| {
+        segment
+          .checked_sub(a.start)
+          .and_then(|i| a.segments.get(i))
+          .and_then(|t| t.get(timing_method))
+      })
+      .collect::<Vec<f64>>();
+    times.sort_by(f64::total_cmp);
+    SegmentStatistics::from_sorted_times(&times)
+  }
+
+  /// statistics of the full range times
+  pub fn range_statistics(&self, timing_method: TimingMethod) -> SegmentStatistics {
+    let mut times = self
+      .range_attempts()
+      .filter_map(|a| a.time.as_ref().and_then(|t| t.get(timing_method)))
+      .collect::<Vec<f64>>();
+    times.sort_by(f64::total_cmp);
+    SegmentStatistics::from_sorted_times(&times)
+  }
+}
+
+fn statistics_table(lua: &Lua, statistics: &SegmentStatistics) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  table.set("count", statistics.count)?;
+  table.set("average", statistics.average)?;
+  table.set("median", statistics.median)?;
+  table.set("best", statistics.best)?;
+  table.set("worst", statistics.worst)?;
+  table.set("standard_deviation", statistics.standard_deviation)?;
+  Ok(table)
+}
+
+/// sets the `practice` global, which is nil outside of practice mode
+pub fn inject_practice_in_lua(lua: &Lua, practice: Option<&Practice>, run: &Run) -> Result<()> {
+  let Some(practice) = practice else {
+    lua.globals().set("practice", LuaNil)?;
+    return Ok(());
+  };
+
+  let timing_method = practice
+    .timer
+    .read()
+    .map_err(|_| anyhow::Error::msg("couldn't access practice timer"))?
+    .current_timing_method();
+
+  let practice_table = lua.create_table()?;
+  practice_table.set("start_segment", practice.start + 1)?;
+  practice_table.set("end_segment", practice.end + 1)?;
+  practice_table.set("attempt_count", practice.range_attempts().count())?;
+  practice_table.set(
+    "finished_count",
+    practice
+      .range_attempts()
+      .filter(|a| a.time.is_some())
+      .count(),
+  )?;
+  practice_table.set(
+    "range",
+    statistics_table(lua, &practice.range_statistics(timing_method))?,
+  )?;
+
+  let segments_table = lua.create_table()?;
+  for i in practice.start..=practice.end {
+    let segment_table = statistics_table(lua, &practice.segment_statistics(i, timing_method))?;
+    segment_table.set("name", run.segments().get(i).map(|s| s.name()))?;
+    segments_table.push(segment_table)?;
+  }
+  practice_table.set("segments", segments_table)?;
+
+  lua.globals().set("practice", practice_table)?;
+
+  Ok(())
+}
@@ -0,0 +1,156 @@
+//! reset reasons: tagging why attempts were reset
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs,
+  path::Path,
+};
+
+use anyhow::Result;
+use livesplit_core::Run;
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::paths::splits_data_path;
+
+/// reasons offered to new runs
+pub const DEFAULT_REASONS: [&str; 4] = ["Death", "Bad RNG", "Misinput", "Other"];
+
+/// why and where an attempt was reset
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResetTag {
+  pub reason: String,
+  /// index of the segment the attempt was reset in
+  pub segment: usize,
+}
+
+/// reset reasons of a run, stored next to its splits file (`<splits>.resets.ron`) instead of in it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResetReasons {
+  /// reasons offered by the prompt, the `n`th one being tagged by the `ResetWithReason{n}` hotkey
+  pub reasons: Vec<String>,
+  /// tags, keyed by attempt index
+  pub tags: BTreeMap<i32, ResetTag>,
+}
+
+impl Default for ResetReasons {
+  fn default() -> Self {
+    Self {
+      reasons: DEFAULT_REASONS.iter().map(|r| r.to_string()).collect(),
+      tags: BTreeMap::new(),
+    }
+  }
+}
+
+/// counts sorted from the most to the least common reason
+fn sorted_counts(counts: HashMap<&str, usize>) -> Vec<(String, usize)> {
+  let mut counts = counts
+    .into_iter()
+    .map(|(reason, count)| (reason.to_string(), count))
+    .collect::<Vec<(String, usize)>>();
+  counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+  counts
+}
+
+impl ResetReasons {
+  /// loads the reset reasons of a splits file, or the default ones if there's none yet or no splits file
+  pub fn load(splits: Option<&Path>) -> Result<Self> {
+    let Some(splits) = splits else {
+      return Ok(Self::default());
+    };
+    let path = splits_data_path(splits, "resets");
+    if !path.try_exists()? {
+      return Ok(Self::default());
+    }
+    Ok(ron::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  pub fn save(&self, splits: &Path) -> Result<()> {
+    fs::write(splits_data_path(splits, "resets"), ron::to_string(self)?)?;
+    Ok(())
+  }
+
+  /// tags an attempt with the reason at `reason` in the reason list
+  pub fn tag(&mut self, attempt: i32, segment: usize, reason: usize) -> Result<()> {
+    let reason = self
+      .reasons
+      .get(reason)
+      .ok_or(anyhow::Error::msg(format!(
+        "no reset reason {}",
+        reason + 1
+      )))?
+      .clone();
+    self.tags.insert(attempt, ResetTag { reason, segment });
+    Ok(())
+  }
+
+  /// number of resets per reason, the most common first
+  pub fn reason_counts(&self) -> Vec<(String, usize)> {
+    let mut counts = HashMap::new();
+    for tag in self.tags.values() {
+      *counts.entry(tag.reason.as_str()).or_insert(0) += 1;
+    }
+    sorted_counts(counts)
+  }
+
+  /// number of resets per reason in a segment, the most common first
+  pub fn segment_reason_counts(&self, segment: usize) -> Vec<(String, usize)> {
+    let mut counts = HashMap::new();
+    for tag in self.tags.values().filter(|t| t.segment == segment) {
+      *counts.entry(tag.reason.as_str()).or_insert(0) += 1;
+    }
+    sorted_counts(counts)
+  }
+}
+
+fn counts_table(lua: &Lua, counts: &[(String, usize)]) -> Result<LuaTable> {
+  let table = lua.create_table()?;
+  for (reason, count) in counts {
+    let count_table = lua.create_table()?;
+    count_table.set("reason", reason.as_str())?;
+    count_table.set("count", *count)?;
+    table.push(count_table)?;
+  }
+  Ok(table)
+}
+
+/// sets the `reset_reasons` global
+pub fn inject_reset_reasons_in_lua(
+  lua: &Lua,
+  reset_reasons: &ResetReasons,
+  run: &Run,
+) -> Result<()> {
+  let reset_reasons_table = lua.create_table()?;
+
+  let reasons_table = lua.create_table()?;
+  for reason in &reset_reasons.reasons {
+    reasons_table.push(reason.as_str())?;
+  }
+  reset_reasons_table.set("reasons", reasons_table)?;
+
+  let tags_table = lua.create_table()?;
+  for (attempt, tag) in &reset_reasons.tags {
+    let tag_table = lua.create_table()?;
+    tag_table.set("attempt", *attempt)?;
+    tag_table.set("segment", tag.segment + 1)?;
+    tag_table.set("reason", tag.reason.as_str())?;
+    tags_table.push(tag_table)?;
+  }
+  reset_reasons_table.set("tags", tags_table)?;
+
+  reset_reasons_table.set("counts", counts_table(lua, &reset_reasons.reason_counts())?)?;
+
+  let segments_table = lua.create_table()?;
+  for i in 0..run.len() {
+    let counts = reset_reasons.segment_reason_counts(i);
+    let segment_table = lua.create_table()?;
+    segment_table.set("most_common", counts.first().map(|(r, _)| r.as_str()))?;
+    segment_table.set("counts", counts_table(lua, &counts)?)?;
+    segments_table.push(segment_table)?;
+  }
+  reset_reasons_table.set("segments", segments_table)?;
+
+  lua.globals().set("reset_reasons", reset_reasons_table)?;
+
+  Ok(())
+}
@@ -0,0 +1,228 @@
+//! statistics computed from a run's attempt history and segment history
+
+use livesplit_core::{Run, TimingMethod};
+use serde::Serialize;
+
+/// summary of a single attempt from the attempt history
+#[derive(Clone, Debug, Serialize)]
+pub struct AttemptSummary {
+  pub index: i32,
+  /// start date, formatted as `YYYY-MM-DD HH:MM` (UTC)
+  pub started: Option<String>,
+  /// real duration of the attempt, pauses included
+  pub duration: Option<f64>,
+  /// final time of the attempt, only present if the attempt was finished with a time for the timing method
+  pub time: Option<f64>,
+  /// index of the segment the attempt was reset in, if it wasn't finished
+  ///
+  /// attempts are finished when they have a final time for any timing method, as game time can be missing
+  pub reset_segment: Option<usize>,
+  pub is_personal_best: bool,
+}
+
+impl AttemptSummary {
+  /// summarizes every attempt of the run, in attempt history order
+  pub fn from_run(run: &Run, timing_method: TimingMethod) -> Vec<Self> {
+    let attempts = run.attempt_history();
+
+    let personal_best = attempts
+      .iter()
+      .filter_map(|a| a.time()[timing_method].map(|t| (a.index(), t.total_seconds())))
+      .min_by(|a, b| a.1.total_cmp(&b.1))
+      .map(|(index, _)| index);
+
+    attempts
+      .iter()
+      .map(|attempt| {
+        let time = attempt.time()[timing_method].map(|t| t.total_seconds());
+        let finished = time.is_some() || attempt.time().real_time.is_some();
+        Self {
+          index: attempt.index(),
+          started: attempt.started().map(|s| {
+            format!(
+              "{:04}-{:02}-{:02} {:02}:{:02}",
+              s.time.year(),
+              s.time.month() as u8,
+              s.time.day(),
+              s.time.hour(),
+              s.time.minute()
+            )
+          }),
+          duration: attempt.duration().map(|d| d.total_seconds()),
+          time,
+          reset_segment: if finished {
+            None
+          } else {
+            Some(reset_segment(run, attempt.index()))
+          },
+          is_personal_best: personal_best == Some(attempt.index()),
+        }
+      })
+      .collect()
+  }
+}
+
+/// finds the segment in which an attempt was reset
+///
+/// this is the segment right after the last one which has an entry for this attempt in its history
+pub fn reset_segment(run: &Run, attempt_index: i32) -> usize {
+  run
+    .segments()
+    .iter()
+    .rposition(|s| s.segment_history().get(attempt_index).is_some())
+    .map(|i| i + 1)
+    .unwrap_or(0)
+    .min(run.len().saturating_sub(1))
+}
+
+/// statistics of a single segment, computed from its segment history
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentStatistics {
+  pub name: String,
+  /// number of times this segment was completed
+  pub count: usize,
+  /// number of times this segment's best time was improved
+  pub golds: usize,
+  /// number of attempts reset in this segment
+  pub resets: usize,
+  pub average: Option<f64>,
+  pub median: Option<f64>,
+  pub best: Option<f64>,
+  pub worst: Option<f64>,
+  pub standard_deviation: Option<f64>,
+}
+
+impl SegmentStatistics {
+  /// computes statistics for every segment of the run
+  pub fn from_run(run: &Run, timing_method: TimingMethod) -> Vec<Self> {
+    let mut resets = vec![0; run.len()];
+    for attempt in AttemptSummary::from_run(run, timing_method) {
+      if let Some(segment) = attempt.reset_segment {
+        if let Some(r) = resets.get_mut(segment) {
+          *r += 1;
+        }
+      }
+    }
+
+    run
+      .segments()
+      .iter()
+      .zip(resets)
+      .map(|(segment, resets)| {
+        let mut history = segment
+          .segment_history()
+          .iter()
+          .filter_map(|(index, time)| time[timing_method].map(|t| (*index, t.total_seconds())))
+          .collect::<Vec<(i32, f64)>>();
+        history.sort_by_key(|(index, _)| *index);
+
+        // a gold is a time beating an earlier best: the first recorded time isn't one, and
+        // imported entries (index <= 0) only set the best to beat
+        let mut golds = 0;
+        let mut best: Option<f64> = None;
+        for (index, time) in &history {
+          match best {
+            Some(b) if *time < b => {
+              if *index > 0 {
+                golds += 1;
+              }
+              best = Some(*time);
+            }
+            Some(_) => {}
+            None => best = Some(*time),
+          }
+        }
+
+        let mut times = history.into_iter().map(|(_, t)| t).collect::<Vec<f64>>();
+        times.sort_by(f64::total_cmp);
+
+        let mut statistics = Self::from_sorted_times(&times);
+        statistics.name = segment.name().to_string();
+        statistics.golds = golds;
+        statistics.resets = resets;
+        statistics
+      })
+      .collect()
+  }
+
+  /// computes statistics from a sorted list of segment times
+  pub fn from_sorted_times(times: &[f64]) -> Self {
+    let count = times.len();
+    if count == 0 {
+      return Self {
+        name: String::new(),
+        count,
+        golds: 0,
+        resets: 0,
+        average: None,
+        median: None,
+        best: None,
+        worst: None,
+        standard_deviation: None,
+      };
+    }
+
+    let average = times.iter().sum::<f64>() / count as f64;
+    let median = if count % 2 == 0 {
+      (times[count / 2 - 1] + times[count / 2]) / 2.
+    } else {
+      times[count / 2]
+    };
+    let variance = times.iter().map(|t| (t - average).powi(2)).sum::<f64>() / count as f64;
+
+    Self {
+      name: String::new(),
+      count,
+      golds: 0,
+      resets: 0,
+      average: Some(average),
+      median: Some(median),
+      best: times.first().copied(),
+      worst: times.last().copied(),
+      standard_deviation: Some(variance.sqrt()),
+    }
+  }
+}
+
+/// sum of best segments after a given attempt
+#[derive(Clone, Debug, Serialize)]
+pub struct SumOfBestEntry {
+  pub attempt: i32,
+  /// missing as long as a segment has never been completed
+  pub sum_of_best: Option<f64>,
+}
+
+/// computes the evolution of the sum of best segments, attempt after attempt
+pub fn sum_of_best_history(run: &Run, timing_method: TimingMethod) -> Vec<SumOfBestEntry> {
+  let mut bests: Vec<Option<f64>> = vec![None; run.len()];
+
+  let mut attempts = run
+    .attempt_history()
+    .iter()
+    .map(|a| a.index())
+    .collect::<Vec<i32>>();
+  attempts.sort();
+
+  attempts
+    .into_iter()
+    .map(|attempt| {
+      for (segment, best) in run.segments().iter().zip(bests.iter_mut()) {
+        if let Some(time) = segment
+          .segment_history()
+          .get(attempt)
+          .and_then(|t| t[timing_method])
+        {
+          let time = time.total_seconds();
+          if best.map(|b| time < b).unwrap_or(true) {
+            *best = Some(time);
+          }
+        }
+      }
+
+      SumOfBestEntry {
+        attempt,
+        sum_of_best: bests.iter().copied().sum(),
+      }
+    })
+    .collect()
+}
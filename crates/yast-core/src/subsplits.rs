@@ -0,0 +1,89 @@
+//! subsplit groups, following LiveSplit's naming convention
+//!
+//! segments whose name starts with `-` are subsplits of the group ended by the next regular segment.
+//! the last segment of a group may be named `{Group Name} Segment Name`
+
+/// position of a segment in the subsplit structure
+#[derive(Clone, Debug)]
+pub struct SubsplitSegment {
+  pub group: usize,
+  pub is_group_end: bool,
+  /// name without the `-` prefix and the `{Group}` header
+  pub display_name: String,
+}
+
+/// group of segments, a regular segment being a group on its own
+#[derive(Clone, Debug)]
+pub struct SubsplitGroup {
+  pub name: String,
+  pub first_segment: usize,
+  pub last_segment: usize,
+}
+
+impl SubsplitGroup {
+  /// whether the group holds subsplits, or is just a regular segment
+  pub fn has_subsplits(&self) -> bool {
+    self.first_segment != self.last_segment
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Subsplits {
+  pub segments: Vec<SubsplitSegment>,
+  pub groups: Vec<SubsplitGroup>,
+}
+
+/// splits a `{Group} Name` segment name in its group name and display name
+fn parse_group_header(name: &str) -> Option<(String, String)> {
+  let rest = name.strip_prefix('{')?;
+  let (group, display) = rest.split_once('}')?;
+  Some((group.trim().to_string(), display.trim().to_string()))
+}
+
+impl Subsplits {
+  pub fn parse<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+    let mut subsplits = Self::default();
+    let mut first_segment = 0;
+
+    for (i, name) in names.into_iter().enumerate() {
+      let group = subsplits.groups.len();
+      if let Some(display_name) = name.strip_prefix('-') {
+        subsplits.segments.push(SubsplitSegment {
+          group,
+          is_group_end: false,
+          display_name: display_name.trim().to_string(),
+        });
+        continue;
+      }
+
+      let (group_name, display_name) =
+        parse_group_header(name).unwrap_or((name.trim().to_string(), name.trim().to_string()));
+      subsplits.segments.push(SubsplitSegment {
+        group,
+        is_group_end: true,
+        display_name,
+      });
+      subsplits.groups.push(SubsplitGroup {
+        name: group_name,
+        first_segment,
+        last_segment: i,
+      });
+      first_segment = i + 1;
+    }
+
+    // trailing subsplits without a group end still form a group
+    if first_segment < subsplits.segments.len() {
+      let last_segment = subsplits.segments.len() - 1;
+      if let Some(last) = subsplits.segments.last_mut() {
+        last.is_group_end = true;
+      }
+      subsplits.groups.push(SubsplitGroup {
+        name: subsplits.segments[last_segment].display_name.clone(),
+        first_segment,
+        last_segment,
+      });
+    }
+
+    subsplits
+  }
+}
@@ -0,0 +1,55 @@
+local PERCENTILE = 0.75
+
+--- @param times number[]
+--- @return number|nil
+local function percentile(times)
+	if #times == 0 then
+		return nil
+	end
+	table.sort(times)
+	local index = math.max(1, math.ceil(#times * PERCENTILE))
+	return times[index]
+end
+
+--- @type comparison
+return {
+	name = "75th Percentile Segments",
+	generate = function(segments)
+		local split_times = {}
+		local total_real_time = 0
+		local total_game_time = 0
+
+		for i, segment in ipairs(segments) do
+			local real_times = {}
+			local game_times = {}
+			for _, entry in ipairs(segment.history) do
+				if entry.real_time ~= nil then
+					table.insert(real_times, entry.real_time)
+				end
+				if entry.game_time ~= nil then
+					table.insert(game_times, entry.game_time)
+				end
+			end
+
+			local real_time = percentile(real_times)
+			local game_time = percentile(game_times)
+			if total_real_time ~= nil and real_time ~= nil then
+				total_real_time = total_real_time + real_time
+			else
+				total_real_time = nil
+			end
+			if total_game_time ~= nil and game_time ~= nil then
+				total_game_time = total_game_time + game_time
+			else
+				total_game_time = nil
+			end
+
+			split_times[i] = {
+				real_time = total_real_time,
+				game_time = total_game_time,
+			}
+		end
+
+		return split_times
+	end,
+}
@@ -36,13 +36,18 @@ local function build_segment_icon(segment_index)
 end
 
 --- @param segment_index number
-local function build_segment_name(segment_index)
+--- @param collapsed boolean whether the segment stands for its whole collapsed group
+local function build_segment_name(segment_index, collapsed)
 	local inner
 	if segment_index > #run.segments then
 		inner = widgets.space():width("shrink"):height("fill"):into()
 	else
 		local segment = run.segments[segment_index]
-		inner = text.write(segment.name, "Split Name")
+		local name = segment.display_name
+		if collapsed then
+			name = run.groups[segment.group].name
+		end
+		inner = text.write(name, "Split Name")
 			:style(text.color("Split Name"))
 			:align_x("left")
 			:align_y(text.align_y("Split Name"))
@@ -126,6 +131,7 @@ return {
 			:number("Total Splits", 10)
 			:number("Upcoming Splits", 1)
 			:boolean("Always Show Last Split", true)
+			:boolean("Collapse Inactive Groups", true)
 			:color("Current Segment Background Color", 0, 0, 1, 1)
 			:boolean("Show Separators", true)
 			:plugin(text.plugin("Split Name", true, false, true))
@@ -154,39 +160,63 @@ return {
 		return result
 	end,
 	widget = function()
-		local segment_center = snapshot.current_split
-		if segment_center == nil then
-			segment_center = 0
+		local current_group = nil
+		if snapshot.current_split ~= nil and run.segments[snapshot.current_split] ~= nil then
+			current_group = run.segments[snapshot.current_split].group
+		end
+
+		-- segments shown, inactive subsplit groups being reduced to their last segment
+		local visible = {}
+		local collapsed = {}
+		for group_index, group in ipairs(run.groups) do
+			if setting("Collapse Inactive Groups") and group.has_subsplits and group_index ~= current_group then
+				table.insert(visible, group.last_segment)
+				collapsed[group.last_segment] = true
+			else
+				for si = group.first_segment, group.last_segment do
+					table.insert(visible, si)
+				end
+			end
+		end
+
+		local segment_center = 0
+		if snapshot.current_split ~= nil then
+			segment_center = #visible + snapshot.current_split - #run.segments
+			for i, si in ipairs(visible) do
+				if si == snapshot.current_split then
+					segment_center = i
+				end
+			end
 		end
 
 		local range = setting("Total Splits")
-		if range < #run.segments and setting("Always Show Last Split") then
+		if range < #visible and setting("Always Show Last Split") then
 			range = range - 1
 		end
 
 		local range_end = segment_center + setting("Upcoming Splits")
 		local range_start = range_end - range + 1
 
-		if range_end > #run.segments then
-			range_start = range_start - (range_end - #run.segments)
-			range_end = #run.segments
+		if range_end > #visible then
+			range_start = range_start - (range_end - #visible)
+			range_end = #visible
 		end
 		if range_start < 1 then
 			range_end = range_end - range_start + 1
 			range_start = 1
 		end
 
-		if range < #run.segments and setting("Always Show Last Split") and range_end == #run.segments then
+		if range < #visible and setting("Always Show Last Split") and range_end == #visible then
 			range = range + 1
 			range_start = range_start - 1
 		end
 
 		local splits_indexes = {}
 		for i = range_start, range_end do
-			table.insert(splits_indexes, i)
+			table.insert(splits_indexes, visible[i])
 		end
-		if range_end < #run.segments and setting("Always Show Last Split") then
-			table.insert(splits_indexes, #run.segments)
+		if range_end < #visible and setting("Always Show Last Split") then
+			table.insert(splits_indexes, visible[#visible])
 		end
 
 		local stack = {}
@@ -228,7 +258,7 @@ return {
 
 		local splits_name = {}
 		for i, si in ipairs(splits_indexes) do
-			table.insert(splits_name, build_segment_name(si))
+			table.insert(splits_name, build_segment_name(si, collapsed[si] == true))
 			if setting("Show Separators") and i < #splits_indexes then
 				table.insert(
 					splits_name,
@@ -45,6 +45,7 @@ local snapshot_current_time = {}
 --- @field current_split number | nil
 --- @field current_timing_method string
 --- @field current_time snapshot_current_time
+--- @field countdown number | nil seconds left before the timer reaches 0, nil once it did
 snapshot = {}
 
 --- @class run_metadata
@@ -52,6 +53,8 @@ snapshot = {}
 --- @field platform_name string
 --- @field uses_emulator boolean
 --- @field region_name string
+--- @field speedrun_com_variables table<string, string>
+--- @field custom_variables table<string, string> permanent variables and the ones set by autosplitters
 local run_metadata = {}
 
 --- @class run_segment_comparison
@@ -59,19 +62,52 @@ local run_metadata = {}
 --- @field game_time number
 local run_segment_comparison = {}
 
+--- @class run_segment_history_entry
+--- @field attempt number
+--- @field real_time number | nil
+--- @field game_time number | nil
+local run_segment_history_entry = {}
+
 --- @class run_segment
 --- @field name string
 --- @field icon userdata
 --- @field comparisons table<string, run_segment_comparison>
+--- @field history run_segment_history_entry[]
+--- @field group number index in `run.groups`
+--- @field is_group_end boolean
+--- @field display_name string name without the subsplit `-` prefix and `{Group}` header
 local run_segment = {}
 
+--- @class run_group
+--- @field name string
+--- @field first_segment number
+--- @field last_segment number
+--- @field has_subsplits boolean false for regular segments, which are groups on their own
+local run_group = {}
+
+--- @class run_attempt_time
+--- @field real_time number | nil
+--- @field game_time number | nil
+local run_attempt_time = {}
+
+--- @class run_attempt
+--- @field index number
+--- @field started number | nil unix timestamp
+--- @field ended number | nil unix timestamp
+--- @field duration number | nil
+--- @field pause_time number | nil
+--- @field time run_attempt_time only set if the attempt was finished
+local run_attempt = {}
+
 --- @class run
 --- @field game_name string
 --- @field game_icon userdata
 --- @field category_name string
 --- @field attempt_count number
 --- @field metadata run_metadata
+--- @field attempts run_attempt[]
 --- @field segments run_segment[]
+--- @field groups run_group[]
 run = {}
 
 --- @class widgets
@@ -89,6 +125,7 @@ local widget = {}
 --- @field width fun(self: widget_text, type: "fill"|"fill_portion"|"shrink"|"fixed", unit: number|nil): widget_text
 --- @field height fun(self: widget_text, type: "fill"|"fill_portion"|"shrink"|"fixed", unit: number|nil): widget_text
 --- @field font fun(self: widget_text, font: string): widget_text
+--- @field transition fun(self: widget_text, from: number[], to: number[], progress: number, easing: easing|nil): widget_text sets the color between `from` and `to`
 
 --- @param content string
 --- @return widget_text
@@ -102,6 +139,7 @@ function widgets.text(content) end
 --- @field filter_method fun(self: widget_image, method: "linear"|"nearest"): widget_image
 --- @field opacity fun(self: widget_image, opacity: number): widget_image
 --- @field crop fun(self: widget_image, x: number, y: number, width: number, height: number): widget_image
+--- @field transition fun(self: widget_image, from: number, to: number, progress: number, easing: easing|nil): widget_image sets the opacity between `from` and `to`
 
 --- @param handle userdata
 --- @return widget_image
@@ -142,6 +180,7 @@ function widgets.row(children) end
 --- @field align_y fun(self: widget_container, alignment: "top"|"bottom"|"center"): widget_container
 --- @field clip fun(self: widget_container, clip: boolean): widget_container
 --- @field style fun(self: widget_container, text_color: number[]|nil, background_color: number[]|nil): widget_container
+--- @field transition fun(self: widget_container, from: number[], to: number[], progress: number, easing: easing|nil): widget_container sets the background color between `from` and `to`
 
 --- @param child widget
 --- @return widget_container
@@ -235,3 +274,152 @@ local analysis_pb_chance = {}
 --- @field sum_of_worst_segments analysis_sum_segments
 --- @field total_playtime number
 analysis = {}
+
+--- values of a single timer
+--- @class timer_values
+--- @field snapshot snapshot
+--- @field run run
+--- @field analysis analysis
+local timer_values = {}
+
+--- values of every timer, the first one being the main timer
+--- `snapshot`, `run` and `analysis` are set to the values of the timer picked by the component
+--- @type timer_values[]
+timers = {}
+
+--- @class practice_statistics
+--- @field count integer
+--- @field average number | nil
+--- @field median number | nil
+--- @field best number | nil
+--- @field worst number | nil
+--- @field standard_deviation number | nil
+local practice_statistics = {}
+
+--- @class practice_segment: practice_statistics
+--- @field name string
+local practice_segment = {}
+
+--- practice mode values, nil outside of practice mode
+--- @class practice
+--- @field start_segment integer
+--- @field end_segment integer
+--- @field attempt_count integer
+--- @field finished_count integer
+--- @field range practice_statistics statistics of the full range times
+--- @field segments practice_segment[]
+--- @type practice | nil
+practice = nil
+
+--- @class reset_reason_tag
+--- @field attempt integer attempt index, as in `run.attempts`
+--- @field segment integer segment the attempt was reset in
+--- @field reason string
+local reset_reason_tag = {}
+
+--- @class reset_reason_count
+--- @field reason string
+--- @field count integer
+local reset_reason_count = {}
+
+--- @class reset_reasons_segment
+--- @field most_common string | nil
+--- @field counts reset_reason_count[] most common reason first
+local reset_reasons_segment = {}
+
+--- reasons attempts were reset for, tagged with hotkeys or the reset prompt
+--- @class reset_reasons
+--- @field reasons string[]
+--- @field tags reset_reason_tag[]
+--- @field counts reset_reason_count[] most common reason first
+--- @field segments reset_reasons_segment[]
+reset_reasons = {}
+
+--- @class comparison_segment_time
+--- @field real_time number | nil
+--- @field game_time number | nil
+local comparison_segment_time = {}
+
+--- @class comparison_segment_history_entry
+--- @field attempt integer
+--- @field real_time number | nil
+--- @field game_time number | nil
+local comparison_segment_history_entry = {}
+
+--- @class comparison_segment
+--- @field name string
+--- @field best_segment comparison_segment_time
+--- @field history comparison_segment_history_entry[]
+local comparison_segment = {}
+
+--- table returned by the scripts of the comparisons directory
+--- comparisons run in their own environment, with the same restrictions as components
+--- @class comparison
+--- @field name string
+--- @field capabilities ("time"|"files")[] | nil capabilities needed by the comparison, available in `generate`. `files` reads from `yast/comparisons/<comparison name>`
+--- @field generate fun(segments: comparison_segment[]): comparison_segment_time[] cumulative split times, one per segment
+local comparison = {}
+
+--- @class marathon_time
+--- @field real_time number | nil
+--- @field game_time number | nil
+local marathon_time = {}
+
+--- @class marathon_category
+--- @field game_name string
+--- @field category_name string
+--- @field personal_best marathon_time
+--- @field final_time marathon_time | nil only present for the categories done in the current attempt
+local marathon_category = {}
+
+--- marathon values, nil when no marathon is loaded
+--- `run` and `snapshot` hold the values of the current category
+--- @class marathon
+--- @field name string
+--- @field current_category integer
+--- @field category_count integer
+--- @field total_time marathon_time
+--- @field personal_best marathon_time sum of the categories' personal bests
+--- @field categories marathon_category[]
+--- @type marathon | nil
+marathon = nil
+
+--- table returned by the scripts of the components directory
+--- components run in their own environment, without `io`, `os`, `debug`, `package`, `load`, `loadfile`, `dofile`, `rawset` and `collectgarbage`
+--- @class component
+--- @field name string
+--- @field author string
+--- @field capabilities ("time"|"files")[] | nil capabilities needed by the component, available in its functions
+--- @field memoize boolean | nil whether the widget is reused while the values it read are unchanged, true by default unless the component declares the `time` capability. reads of `state` and of the current time aren't tracked
+--- @field persist string[] | nil keys of `state` saved to disk, and restored when the layout is loaded again
+--- @field settings fun(): settings_factory
+--- @field widget fun(): widget
+local component = {}
+
+--- only available to components declaring the `files` capability
+--- @class files
+--- @field read fun(path: string): string reads a file, relative to the component's folder (`yast/components/<component name>`)
+files = {}
+
+--- per-instance table surviving across builds of the component, reset when the layout is loaded
+--- only the keys listed in the component's `persist` field are saved, when YAST closes or loads another layout
+--- @type table
+state = {}
+
+--- @class frame
+--- @field time number monotonic time since the first frame, in seconds
+--- @field delta number time since the previous frame, in seconds
+--- updated on every redraw, at the layout's max refresh rate while a timer is running and 10 Hz otherwise
+--- @type frame
+frame = {}
+
+--- @alias easing "linear"|"in_quad"|"out_quad"|"in_out_quad"|"in_cubic"|"out_cubic"|"in_out_cubic"|"in_sine"|"out_sine"|"in_out_sine"|"in_expo"|"out_expo"|"out_back"|"out_bounce"
+
+--- easing and interpolation helpers, progresses being clamped between 0 and 1 and easings linear by default
+--- @class tween
+--- @field easings easing[]
+--- @field ease fun(progress: number, easing: easing|nil): number
+--- @field lerp fun(from: number, to: number, progress: number, easing: easing|nil): number
+--- @field color fun(from: number[], to: number[], progress: number, easing: easing|nil): number[]
+--- @field progress fun(start: number, duration: number, now: number): number progress of an animation started at `start`, usually with `frame.time` as `now`
+tween = {}
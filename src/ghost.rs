@@ -0,0 +1,127 @@
+//! import of another run's splits as a comparison
+
+use std::{fmt::Display, path::Path};
+
+use iced::{
+  Element, Length,
+  widget::{button, column, pick_list, row, scrollable, text, text_input},
+};
+use livesplit_core::Run;
+use yast_core::comparisons::match_segments_by_name;
+
+use crate::{AppMessage, menu::MenuMessage};
+
+/// segment of the ghost run that can be picked for a segment of the current run
+#[derive(Clone, Debug, PartialEq)]
+pub struct GhostSegmentOption {
+  pub index: Option<usize>,
+  name: String,
+}
+
+impl Display for GhostSegmentOption {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.index {
+      Some(i) => write!(f, "{}. {}", i + 1, self.name),
+      None => f.write_str("(none)"),
+    }
+  }
+}
+
+/// ghost run being imported, with the segment mapping edited by the user
+pub struct GhostImport {
+  pub ghost: Run,
+  pub name: String,
+  pub mapping: Vec<Option<usize>>,
+  segment_names: Vec<String>,
+  options: Vec<GhostSegmentOption>,
+}
+
+impl GhostImport {
+  pub fn new(run: &Run, ghost: Run, path: &Path) -> Self {
+    let mut options = vec![GhostSegmentOption {
+      index: None,
+      name: String::new(),
+    }];
+    options.extend(
+      ghost
+        .segments()
+        .iter()
+        .enumerate()
+        .map(|(i, s)| GhostSegmentOption {
+          index: Some(i),
+          name: s.name().to_string(),
+        }),
+    );
+
+    let name = path
+      .file_stem()
+      .map(|s| s.to_string_lossy().to_string())
+      .unwrap_or_default();
+
+    Self {
+      mapping: match_segments_by_name(run, &ghost),
+      ghost,
+      name,
+      segment_names: run
+        .segments()
+        .iter()
+        .map(|s| s.name().to_string())
+        .collect(),
+      options,
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, AppMessage> {
+    let mut children: Vec<Element<'_, AppMessage>> = Vec::new();
+
+    children.push(
+      text_input("Comparison Name", &self.name)
+        .on_input(|s| AppMessage::MenuMessage(MenuMessage::ChangeGhostName(s)))
+        .into(),
+    );
+
+    let unmatched = self.mapping.iter().filter(|m| m.is_none()).count();
+    if unmatched > 0 {
+      children.push(
+        text(format!(
+          "{} segment(s) couldn't be matched by name",
+          unmatched
+        ))
+        .size(10.)
+        .into(),
+      );
+    }
+
+    let mut segments: Vec<Element<'_, AppMessage>> = Vec::new();
+    for (i, (name, index)) in self.segment_names.iter().zip(&self.mapping).enumerate() {
+      segments.push(
+        row(vec![
+          text(name).width(Length::FillPortion(1)).into(),
+          pick_list(
+            self.options.as_slice(),
+            Some(&self.options[index.map(|j| j + 1).unwrap_or(0)]),
+            move |o| AppMessage::MenuMessage(MenuMessage::ChangeGhostSegment(i, o)),
+          )
+          .width(Length::FillPortion(1))
+          .into(),
+        ])
+        .spacing(5.)
+        .into(),
+      );
+    }
+    children.push(
+      scrollable(column(segments).spacing(2.))
+        .height(Length::Fill)
+        .into(),
+    );
+
+    children.push(
+      button("Import")
+        .on_press(AppMessage::MenuMessage(MenuMessage::ConfirmGhostImport))
+        .style(button::success)
+        .into(),
+    );
+
+    column(children).spacing(5.).width(Length::Fill).into()
+  }
+}
@@ -0,0 +1,163 @@
+//! attempt history browser and segment statistics
+
+use iced::{
+  Color, Element, Length,
+  widget::{column, row, scrollable, text},
+};
+use livesplit_core::{Timer, TimingMethod};
+use yast_core::statistics::{AttemptSummary, SegmentStatistics};
+
+use crate::AppMessage;
+
+/// history data, computed when opening the history screen
+pub struct History {
+  timing_method: TimingMethod,
+  attempts: Vec<AttemptSummary>,
+  segments: Vec<SegmentStatistics>,
+  segment_names: Vec<String>,
+}
+
+impl History {
+  pub fn from_timer(timer: &Timer) -> Self {
+    let run = timer.run();
+    let timing_method = timer.current_timing_method();
+
+    Self {
+      timing_method,
+      attempts: AttemptSummary::from_run(run, timing_method),
+      segments: SegmentStatistics::from_run(run, timing_method),
+      segment_names: run
+        .segments()
+        .iter()
+        .map(|s| s.name().to_string())
+        .collect(),
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, AppMessage> {
+    let mut children: Vec<Element<'_, AppMessage>> = Vec::new();
+
+    children.push(
+      text(format!("Attempts ({:?})", self.timing_method))
+        .size(16.)
+        .into(),
+    );
+
+    if self.attempts.is_empty() {
+      children.push(text("no attempts yet").size(10.).into());
+    }
+
+    for attempt in self.attempts.iter().rev() {
+      let outcome = match (attempt.time, attempt.reset_segment) {
+        (Some(time), _) => format_time(Some(time)),
+        (None, Some(segment)) => format!(
+          "reset at {}",
+          self
+            .segment_names
+            .get(segment)
+            .map(|n| n.as_str())
+            .unwrap_or("?")
+        ),
+        (None, None) => String::from("finished"),
+      };
+
+      let mut attempt_row = vec![
+        text(format!("#{}", attempt.index))
+          .width(Length::Fixed(40.))
+          .into(),
+        text(attempt.started.clone().unwrap_or(String::from("-")))
+          .width(Length::Fixed(110.))
+          .into(),
+        text(format_time(attempt.duration))
+          .width(Length::Fixed(70.))
+          .into(),
+        text(outcome).width(Length::Fill).into(),
+      ];
+      if attempt.is_personal_best {
+        attempt_row.push(
+          text("PB")
+            .style(|_| text::Style {
+              color: Some(Color::from_rgb(1.0, 0.8, 0.0)),
+            })
+            .into(),
+        );
+      }
+
+      children.push(row(attempt_row).spacing(5.).into());
+    }
+
+    children.push(text("Segments").size(16.).into());
+
+    children.push(
+      row(vec![
+        text("Name").width(Length::Fill).into(),
+        text("Avg").width(Length::Fixed(60.)).into(),
+        text("Median").width(Length::Fixed(60.)).into(),
+        text("Best").width(Length::Fixed(60.)).into(),
+        text("Worst").width(Length::Fixed(60.)).into(),
+        text("Std Dev").width(Length::Fixed(60.)).into(),
+      ])
+      .spacing(5.)
+      .into(),
+    );
+
+    for segment in &self.segments {
+      children.push(
+        row(vec![
+          text(format!("{} ({})", segment.name, segment.count))
+            .width(Length::Fill)
+            .into(),
+          text(format_time(segment.average))
+            .width(Length::Fixed(60.))
+            .into(),
+          text(format_time(segment.median))
+            .width(Length::Fixed(60.))
+            .into(),
+          text(format_time(segment.best))
+            .width(Length::Fixed(60.))
+            .into(),
+          text(format_time(segment.worst))
+            .width(Length::Fixed(60.))
+            .into(),
+          text(format_time(segment.standard_deviation))
+            .width(Length::Fixed(60.))
+            .into(),
+        ])
+        .spacing(5.)
+        .into(),
+      );
+    }
+
+    scrollable(column(children).spacing(2.).width(Length::Fill))
+      .width(Length::Fill)
+      .height(Length::Fill)
+      .into()
+  }
+}
+
+/// formats seconds as `h:mm:ss.cc`, hiding empty hours and minutes
+pub fn format_time(seconds: Option<f64>) -> String {
+  match seconds {
+    None => String::from("-"),
+    Some(seconds) => {
+      let sign = if seconds < 0. { "-" } else { "" };
+      let total_centiseconds = (seconds.abs() * 100.).floor() as u64;
+      let centiseconds = total_centiseconds % 100;
+      let total_seconds = total_centiseconds / 100;
+      let hours = total_seconds / 3600;
+      let minutes = (total_seconds / 60) % 60;
+      let seconds = total_seconds % 60;
+
+      if hours > 0 {
+        format!(
+          "{}{}:{:02}:{:02}.{:02}",
+          sign, hours, minutes, seconds, centiseconds
+        )
+      } else if minutes > 0 {
+        format!("{}{}:{:02}.{:02}", sign, minutes, seconds, centiseconds)
+      } else {
+        format!("{}{}.{:02}", sign, seconds, centiseconds)
+      }
+    }
+  }
+}
@@ -0,0 +1,104 @@
+//! global hotkey listening
+//!
+//! the hotkey manager is polled by a background thread, which wakes the app when a hotkey is pressed,
+//! so hotkeys don't wait for the next update while YAST updates slowly. the thread ends with the subscription
+
+use std::{
+  collections::VecDeque,
+  hash::{Hash, Hasher},
+  sync::{Arc, Mutex, MutexGuard},
+  thread,
+  time::Duration,
+};
+
+use anyhow::Result;
+use handy_keys::{Hotkey, HotkeyEvent, HotkeyId, HotkeyManager};
+use iced::{Subscription, futures::channel::mpsc, stream};
+
+use crate::AppMessage;
+
+/// time between two polls of the hotkey manager
+///
+/// hotkeys are handled at most this late, which is less than a frame at 60 Hz
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// hotkey manager shared with the polling thread, with the events it received
+#[derive(Clone)]
+pub struct HotkeyListener {
+  manager: Arc<Mutex<HotkeyManager>>,
+  events: Arc<Mutex<VecDeque<HotkeyEvent>>>,
+}
+
+// there is a single listener, so the subscription is identified by its type only
+impl Hash for HotkeyListener {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    "hotkey listener".hash(state);
+  }
+}
+
+impl HotkeyListener {
+  pub fn new() -> Result<Self> {
+    Ok(Self {
+      manager: Arc::new(Mutex::new(HotkeyManager::new()?)),
+      events: Arc::new(Mutex::new(VecDeque::new())),
+    })
+  }
+
+  fn manager(&self) -> Result<MutexGuard<'_, HotkeyManager>> {
+    self
+      .manager
+      .lock()
+      .map_err(|_| anyhow::Error::msg("couldn't access hotkey manager"))
+  }
+
+  pub fn register(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+    Ok(self.manager()?.register(hotkey)?)
+  }
+
+  pub fn unregister(&self, id: HotkeyId) -> Result<()> {
+    self.manager()?.unregister(id)?;
+    Ok(())
+  }
+
+  /// next hotkey event received by the polling thread
+  pub fn try_recv(&self) -> Option<HotkeyEvent> {
+    self.events.lock().ok()?.pop_front()
+  }
+
+  /// moves the events of the manager to the queue, returning whether there were any
+  fn poll(&self) -> bool {
+    let (Ok(manager), Ok(mut events)) = (self.manager.lock(), self.events.lock()) else {
+      return false;
+    };
+    let mut received = false;
+    while let Some(event) = manager.try_recv() {
+      events.push_back(event);
+      received = true;
+    }
+    received
+  }
+
+  /// updates the app as soon as hotkey events are received
+  pub fn subscription(&self) -> Subscription<AppMessage> {
+    Subscription::run_with(self.clone(), |listener| {
+      let listener = listener.clone();
+      stream::channel(1, move |mut output: mpsc::Sender<AppMessage>| async move {
+        thread::spawn(move || {
+          // the receiver is dropped along with the subscription
+          while !output.is_closed() {
+            if listener.poll() {
+              // a full channel already holds an update, which will handle these events too
+              if let Err(err) = output.try_send(AppMessage::Update) {
+                if err.is_disconnected() {
+                  break;
+                }
+              }
+            }
+            thread::sleep(POLL_INTERVAL);
+          }
+        });
+        std::future::pending::<()>().await;
+      })
+    })
+  }
+}
@@ -1,12 +1,19 @@
 use anyhow::Result;
-use handy_keys::{HotkeyId, HotkeyManager};
+use handy_keys::HotkeyId;
 use image::ImageFormat;
 use include_dir::Dir;
 use yast_core::{
+  comparisons::LuaComparison,
   defaults::copy_default_components,
   layout::{HotkeyAction, Layout, component::Component},
-  lua::{LuaContext, inject::inject_values_in_lua},
+  lua::{
+    LuaContext,
+    inject::{inject_frame_in_lua, inject_values_in_lua, invalidate_value_cache},
+  },
+  marathon::{Marathon, inject_marathon_in_lua},
+  practice::{Practice, inject_practice_in_lua},
   repository::Repository,
+  reset_reasons::{ResetReasons, inject_reset_reasons_in_lua},
 };
 
 #[macro_use]
@@ -15,32 +22,49 @@ extern crate log;
 #[cfg(target_os = "windows")]
 use iced::keyboard;
 use iced::{
-  Background, Color, Element, Length, Settings, Size, Subscription, Task, Theme,
+  Background, Color, Element, Event, Length, Settings, Size, Subscription, Task, Theme, event,
   time::every,
-  widget::{container, mouse_area, space, stack, text},
+  widget::{container, mouse_area, space, stack},
   window::{self, icon},
 };
 use livesplit_core::{
-  Run, Segment, SharedTimer, Timer,
+  Run, Segment, SharedTimer, Timer, TimerPhase,
   auto_splitting::Runtime,
   run::saver::livesplit::{IoWrite, save_timer},
 };
-use std::time::Duration;
 use std::{collections::HashMap, fs::File, io::BufWriter, time::SystemTime};
+use std::{
+  path::{Path, PathBuf},
+  time::Duration,
+};
 
-use crate::menu::{Menu, MenuMessage};
+use crate::{
+  hotkeys::HotkeyListener,
+  menu::{Menu, MenuMessage},
+  reset_reasons::PendingReset,
+  watch::{FileWatcher, WatchedFile},
+};
 
+mod ghost;
+mod history;
+mod hotkeys;
 mod menu;
+mod metadata;
+mod practice;
+mod reset_reasons;
 mod update;
+mod watch;
 
 static VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct App {
   window_id: Option<window::Id>,
-  hotkey_manager: HotkeyManager,
-  hotkeys: HashMap<HotkeyId, HotkeyAction>,
+  hotkey_manager: HotkeyListener,
+  /// registered hotkeys, with the index of the timer they control
+  hotkeys: HashMap<HotkeyId, (usize, HotkeyAction)>,
   hotkeys_on: bool,
   components: HashMap<String, String>,
+  lua_comparisons: Vec<LuaComparison>,
   lua_context: LuaContext,
   pub layout: Layout,
   repository: Repository,
@@ -48,9 +72,38 @@ pub struct App {
   #[allow(unused)]
   autosplitter: Runtime,
   splits_edited: bool,
+  /// splits file of the run, which its reset reasons and practice history are stored next to
+  splits_path: Option<PathBuf>,
+  practice: Option<Practice>,
+  marathon: Option<Marathon>,
+  extra_timers: Vec<ExtraTimer>,
+  reset_reasons: ResetReasons,
+  /// last reset attempt, while its reason is asked for
+  pending_reset: Option<PendingReset>,
+  watcher: FileWatcher,
   menu: Menu,
 }
 
+/// additional timer with its own splits, for co-op and races
+pub struct ExtraTimer {
+  pub timer: SharedTimer,
+  repository: Repository,
+  /// whether attempts were recorded since the splits were loaded or saved
+  edited: bool,
+}
+
+impl ExtraTimer {
+  pub fn new(run: Run) -> Result<Self> {
+    let mut repository = Repository::default();
+    repository.update_from_splits(&run)?;
+    Ok(Self {
+      timer: Timer::new(run)?.into_shared(),
+      repository,
+      edited: false,
+    })
+  }
+}
+
 #[derive(Clone, Debug)]
 pub enum AppMessage {
   Init(Option<window::Id>),
@@ -58,6 +111,9 @@ pub enum AppMessage {
 
   WindowClosing(window::Id),
   WindowResized((window::Id, Size)),
+  FileDropped(PathBuf),
+  TagReset(usize),
+  DismissResetPrompt,
   #[cfg(target_os = "windows")]
   KeyboardEvent(keyboard::Event),
   ResizeTimer(f32, f32),
@@ -67,7 +123,7 @@ pub enum AppMessage {
 
 impl App {
   fn new() -> (Self, Task<AppMessage>) {
-    let hotkey_manager = HotkeyManager::new().expect("couldn't initialize hotkeys");
+    let hotkey_manager = HotkeyListener::new().expect("couldn't initialize hotkeys");
     let hotkeys = HashMap::new();
 
     let mut run = Run::new();
@@ -91,6 +147,17 @@ impl App {
     )
     .expect("couldn't get components");
 
+    let mut comparisons_dir = dirs::data_dir().expect("couldn't get data directory");
+    comparisons_dir.push("yast/comparisons");
+    let lua_comparisons = LuaComparison::import_all_from_directory(
+      &comparisons_dir.to_string_lossy().to_string(),
+      &lua_context.lua,
+    )
+    .unwrap_or_else(|err| {
+      error!("couldn't get lua comparisons: {}", err);
+      Vec::new()
+    });
+
     (
       Self {
         window_id: None,
@@ -100,6 +167,7 @@ impl App {
         hotkeys_on: false,
 
         components,
+        lua_comparisons,
         lua_context,
 
         layout: Layout::default(),
@@ -109,6 +177,13 @@ impl App {
         autosplitter,
 
         splits_edited: false,
+        splits_path: None,
+        practice: None,
+        marathon: None,
+        extra_timers: Vec::new(),
+        reset_reasons: ResetReasons::default(),
+        pending_reset: None,
+        watcher: FileWatcher::new(),
 
         menu: Menu::new(comparisons),
       },
@@ -117,27 +192,36 @@ impl App {
   }
 
   fn view(&self) -> Element<'_, AppMessage> {
+    let guards = std::iter::once((self.active_timer(), self.active_repository()))
+      .chain(self.extra_timers.iter().map(|t| (&t.timer, &t.repository)))
+      .filter_map(|(t, r)| t.read().ok().map(|g| (g, r)))
+      .collect::<Vec<_>>();
+    let timers = guards
+      .iter()
+      .map(|(g, r)| (&**g, *r))
+      .collect::<Vec<(&Timer, &Repository)>>();
+    inject_values_in_lua(&self.lua_context.lua, &timers)
+      .unwrap_or_else(|err| error!("couldn't inject values into lua: {}", err));
+    inject_frame_in_lua(&self.lua_context.lua)
+      .unwrap_or_else(|err| error!("couldn't inject frame clock into lua: {}", err));
+    drop(timers);
+    drop(guards);
     if let Ok(timer) = self.timer.read() {
-      inject_values_in_lua(&self.lua_context.lua, &timer, &self.repository)
-        .unwrap_or_else(|err| error!("couldn't inject values into lua: {}", err));
+      inject_practice_in_lua(&self.lua_context.lua, self.practice.as_ref(), timer.run())
+        .unwrap_or_else(|err| error!("couldn't inject practice values into lua: {}", err));
+      inject_marathon_in_lua(&self.lua_context.lua, self.marathon.as_ref(), &timer)
+        .unwrap_or_else(|err| error!("couldn't inject marathon values into lua: {}", err));
+      inject_reset_reasons_in_lua(&self.lua_context.lua, &self.reset_reasons, timer.run())
+        .unwrap_or_else(|err| error!("couldn't inject reset reasons into lua: {}", err));
     }
 
     let inner = if let Some(lcontent) = &self.layout.content {
-      lcontent
-        .build(
-          &self.lua_context.lua,
-          vec![],
-          &self.layout.settings,
-          &self.repository,
-        )
-        .unwrap_or_else(|err| {
-          error!("couldn't build layout: {}", err);
-          text("couldn't build layout, please check the logs for full details")
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .center()
-            .into()
-        })
+      lcontent.build(
+        &self.lua_context.lua,
+        vec![],
+        &self.layout.settings,
+        &self.repository,
+      )
     } else {
       space().width(Length::Fill).height(Length::Fill).into()
     };
@@ -164,6 +248,8 @@ impl App {
           .into(),
       );
       stack_vec.push(Menu::view(&self));
+    } else if self.pending_reset.is_some() {
+      stack_vec.push(reset_reasons::prompt(&self));
     }
 
     let stacked = stack(stack_vec).into();
@@ -175,12 +261,26 @@ impl App {
     format!("YAST {}", VERSION)
   }
 
+  /// update rate, depending on whether a timer is running
+  fn refresh_rate(&self) -> f64 {
+    let phases = std::iter::once(self.active_timer())
+      .chain(self.extra_timers.iter().map(|t| &t.timer))
+      .filter_map(|t| t.read().ok().map(|t| t.current_phase()))
+      .collect::<Vec<TimerPhase>>();
+    self.layout.refresh_rate(phases)
+  }
+
   #[cfg(not(target_os = "windows"))]
   fn subscription(&self) -> Subscription<AppMessage> {
     Subscription::batch(vec![
       window::resize_events().map(AppMessage::WindowResized),
       window::close_requests().map(AppMessage::WindowClosing),
-      every(Duration::from_secs_f64(1.0 / 60.0)).map(|_| AppMessage::Update),
+      event::listen_with(|event, _status, _id| match event {
+        Event::Window(window::Event::FileDropped(path)) => Some(AppMessage::FileDropped(path)),
+        _ => None,
+      }),
+      self.hotkey_manager.subscription(),
+      every(Duration::from_secs_f64(1.0 / self.refresh_rate())).map(|_| AppMessage::Update),
     ])
   }
 
@@ -189,23 +289,202 @@ impl App {
     Subscription::batch(vec![
       window::resize_events().map(AppMessage::WindowResized),
       window::close_requests().map(AppMessage::WindowClosing),
+      event::listen_with(|event, _status, _id| match event {
+        Event::Window(window::Event::FileDropped(path)) => Some(AppMessage::FileDropped(path)),
+        _ => None,
+      }),
       keyboard::listen().map(AppMessage::KeyboardEvent),
-      every(Duration::from_secs_f64(1.0 / 60.0)).map(|_| AppMessage::Update),
+      self.hotkey_manager.subscription(),
+      every(Duration::from_secs_f64(1.0 / self.refresh_rate())).map(|_| AppMessage::Update),
     ])
   }
 
-  fn save_splits(&self, path: String) -> Result<()> {
-    let file = File::create(path)?;
-    let writer = BufWriter::new(file);
-    {
+  /// registers the layout's hotkeys of a timer, by timer index
+  fn register_timer_hotkeys(&mut self, timer_index: usize) -> Result<()> {
+    if let Some(hotkeys) = self.layout.timer_hotkeys(timer_index) {
+      for (action, hotkey) in hotkeys {
+        self.hotkeys.insert(
+          self.hotkey_manager.register(hotkey.clone())?,
+          (timer_index, action.clone()),
+        );
+      }
+    }
+    Ok(())
+  }
+
+  /// additional timer at a timer index, the main timer being timer 0
+  fn extra_timer_mut(&mut self, timer_index: usize) -> Result<&mut ExtraTimer> {
+    timer_index
+      .checked_sub(1)
+      .and_then(|i| self.extra_timers.get_mut(i))
+      .ok_or(anyhow::Error::msg(format!(
+        "couldn't find timer {}",
+        timer_index + 1
+      )))
+  }
+
+  /// saves the splits of the first timer, which are then the watched splits file unless in a marathon
+  fn save_splits(&mut self, path: String) -> Result<()> {
+    save_splits_of(&self.timer, path.clone())?;
+    if self.marathon.is_none() {
+      // the reset reasons follow the splits, as their attempts are the saved ones
+      self.reset_reasons.save(Path::new(&path))?;
+      self.splits_path = Some(PathBuf::from(&path));
+      self.splits_edited = false;
+      self.watcher.splits = Some(WatchedFile::new(path));
+    }
+    Ok(())
+  }
+
+  /// replaces the timer with a new one for the given run, loaded from the `splits_path` file
+  pub fn load_run(&mut self, run: Run, splits_path: Option<PathBuf>) -> Result<()> {
+    let timer = Timer::new(run)?;
+    self.practice = None;
+    self.menu.reset_practice_range();
+    self.repository.update_from_splits(timer.run())?;
+    self.menu.refresh_comparisons(timer.run());
+    self.reset_reasons = ResetReasons::load(splits_path.as_deref())?;
+    self.splits_path = splits_path;
+    self.pending_reset = None;
+    self.timer = timer.into_shared();
+    self.autosplitter = Runtime::new(self.timer.clone());
+    self.refresh_lua_comparisons()?;
+    invalidate_value_cache(&self.lua_context.lua);
+    Ok(())
+  }
+
+  /// moves the marathon to its next category when needed
+  fn update_marathon(&mut self) -> Result<()> {
+    let Some(marathon) = &mut self.marathon else {
+      return Ok(());
+    };
+
+    let timer_changed = {
+      let mut timer = self
+        .timer
+        .write()
+        .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+      marathon.update(&mut timer)?
+    };
+
+    if timer_changed {
+      self.splits_path = Some(marathon.current_path().to_path_buf());
       let timer = self
         .timer
         .read()
         .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
-      save_timer(&timer, IoWrite(writer))?;
+      self.repository.update_from_splits(timer.run())?;
+      self.menu.refresh_comparisons(timer.run());
+      self.reset_reasons = ResetReasons::load(self.splits_path.as_deref())?;
+      invalidate_value_cache(&self.lua_context.lua);
+      self.splits_edited = true;
+      info!(
+        "marathon category: {} - {}",
+        timer.run().game_name(),
+        timer.run().category_name()
+      );
     }
+
+    Ok(())
+  }
+
+  /// timer displayed and controlled by hotkeys: the practice timer in practice mode, the main one otherwise
+  pub fn active_timer(&self) -> &SharedTimer {
+    match &self.practice {
+      Some(practice) => &practice.timer,
+      None => &self.timer,
+    }
+  }
+
+  /// split icons of the active timer
+  pub fn active_repository(&self) -> &Repository {
+    match &self.practice {
+      Some(practice) => &practice.repository,
+      None => &self.repository,
+    }
+  }
+
+  /// replaces the run held by the timer with a modified copy
+  ///
+  /// this resets the timer, so it is refused while an attempt is going on
+  fn replace_run(&mut self, f: impl FnOnce(&mut Run) -> Result<()>) -> Result<()> {
+    let mut timer = self
+      .timer
+      .write()
+      .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+    if timer.current_phase() != TimerPhase::NotRunning {
+      return Err(anyhow::Error::msg(
+        "can't modify the run while an attempt is going on",
+      ));
+    }
+
+    let mut run = timer.run().clone();
+    f(&mut run)?;
+    timer
+      .set_run(run)
+      .map_err(|_| anyhow::Error::msg("couldn't replace the run in the timer"))?;
+    self.menu.refresh_comparisons(timer.run());
+    invalidate_value_cache(&self.lua_context.lua);
+
+    Ok(())
+  }
+
+  /// common function for user modifications of the run
+  pub fn edit_run(&mut self, f: impl FnOnce(&mut Run) -> Result<()>) -> Result<()> {
+    self.replace_run(f)?;
+    self.splits_edited = true;
     Ok(())
   }
+
+  /// regenerates the lua comparisons enabled in the run
+  ///
+  /// called whenever the history changes, as comparisons are usually computed from it
+  ///
+  /// the run is only replaced if at least one lua comparison is enabled
+  pub fn refresh_lua_comparisons(&mut self) -> Result<()> {
+    if self.lua_comparisons.is_empty() {
+      return Ok(());
+    }
+    let comparisons = {
+      let timer = self
+        .timer
+        .read()
+        .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+      self
+        .lua_comparisons
+        .iter()
+        .filter(|c| c.is_enabled(timer.run()))
+        .cloned()
+        .collect::<Vec<LuaComparison>>()
+    };
+    if comparisons.is_empty() {
+      return Ok(());
+    }
+
+    let lua = self.lua_context.lua.clone();
+    // a failing comparison keeps its previous split times, without preventing the others from refreshing
+    self.replace_run(|run| {
+      for comparison in &comparisons {
+        comparison
+          .apply(&lua, run)
+          .unwrap_or_else(|err| error!("couldn't refresh comparison {}: {}", comparison.name, err));
+      }
+      Ok(())
+    })
+  }
+}
+
+/// saves the splits of a timer
+fn save_splits_of(timer: &SharedTimer, path: String) -> Result<()> {
+  let file = File::create(path)?;
+  let writer = BufWriter::new(file);
+  {
+    let timer = timer
+      .read()
+      .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+    save_timer(&timer, IoWrite(writer))?;
+  }
+  Ok(())
 }
 
 pub fn run_app() -> iced::Result {
@@ -1,30 +1,79 @@
 use std::{
   fmt::Display,
   fs::{self, read_to_string},
-  path::Path,
+  path::{Path, PathBuf},
 };
 
 use anyhow::Result;
 use iced::{
   Background, Color, Element, Length, Task,
-  alignment::Horizontal,
-  widget::{button, column, combo_box, container, image, opaque, row, space, stack, text},
+  alignment::{Horizontal, Vertical},
+  widget::{button, column, combo_box, container, image, opaque, row, slider, space, stack, text},
+};
+use livesplit_core::{Run, TimerPhase, TimingMethod, run::parser};
+use yast_core::{
+  comparisons::{OPTIONAL_GENERATORS, has_generator, import_ghost, toggle_generator},
+  export::{export_statistics_csv, export_statistics_json},
+  layout::{HotkeyAction, Layout, memo::invalidate_memos, state::save_states},
+  lua::inject::invalidate_value_cache,
+  marathon::Marathon,
+  practice::Practice,
+};
+
+use crate::{
+  App, AppMessage, ExtraTimer,
+  ghost::{GhostImport, GhostSegmentOption},
+  history::History,
+  metadata::{MetadataEditor, MetadataMessage},
+  practice, save_splits_of,
+  watch::WatchedFile,
 };
-use livesplit_core::{Timer, TimingMethod, auto_splitting::Runtime, run::parser};
-use yast_core::layout::Layout;
 
-use crate::{App, AppMessage};
+/// extensions of the splits formats livesplit-core can parse
+pub const SPLITS_EXTENSIONS: &[&str] = &[
+  "lss",
+  "wsplit",
+  "lfs",
+  "llanfair",
+  "splits",
+  "json",
+  "timesplittracker",
+  "fs",
+  "txt",
+  "sps",
+];
 
 pub struct Menu {
   pub opened: bool,
+  screen: MenuScreen,
   sapho_handle: image::Handle,
   comparison_state: combo_box::State<String>,
   timing_method_state: combo_box::State<TimingMethodOption>,
+  history: Option<History>,
+  ghost: Option<GhostImport>,
+  practice_start: usize,
+  practice_end: usize,
+  metadata: Option<MetadataEditor>,
+  /// format the current splits were parsed from
+  splits_format: Option<String>,
+}
+
+/// screens available in the menu
+#[derive(Clone, Debug)]
+pub enum MenuScreen {
+  Main,
+  History,
+  Comparisons,
+  Ghost,
+  Practice,
+  Timers,
+  Metadata,
 }
 
 #[derive(Clone, Debug)]
 pub enum MenuMessage {
   ToggleMenu,
+  OpenScreen(MenuScreen),
 
   ToggleHotkeys,
   LoadSplitsOpenPicker,
@@ -37,9 +86,36 @@ pub enum MenuMessage {
   SaveLayout(String),
   LoadAutosplitterOpenPicker,
   LoadAutosplitter(String),
+  ExportStatisticsJsonOpenPicker,
+  ExportStatisticsJson(String),
+  ExportStatisticsCsvOpenPicker,
+  ExportStatisticsCsv(String),
 
   ChangeComparison(String),
   ChangeTimingMethod(TimingMethodOption),
+  ChangeMaxRefreshRate(f64),
+  ToggleComparisonGenerator(String),
+  ToggleLuaComparison(String),
+  ImportGhostOpenPicker,
+  ImportGhost(String),
+  ChangeGhostName(String),
+  ChangeGhostSegment(usize, GhostSegmentOption),
+  ConfirmGhostImport,
+  ChangePracticeStart(usize),
+  ChangePracticeEnd(usize),
+  StartPractice,
+  StopPractice,
+  LoadMarathonOpenPicker,
+  LoadMarathon(String),
+  SaveMarathon,
+  StopMarathon,
+  AddTimerOpenPicker,
+  AddTimer(String),
+  RemoveTimer(usize),
+  SaveTimerOpenPicker(usize),
+  SaveTimer(usize, String),
+  Metadata(MetadataMessage),
+  ApplyMetadata,
 }
 
 #[derive(Clone, Debug)]
@@ -79,19 +155,67 @@ impl Menu {
   pub fn new(comparisons: Vec<String>) -> Self {
     Self {
       opened: false,
+      screen: MenuScreen::Main,
       sapho_handle: image::Handle::from_bytes(include_bytes!("../res/sapho_logo.png").to_vec()),
       comparison_state: combo_box::State::new(comparisons),
       timing_method_state: combo_box::State::new(vec![
         TimingMethodOption::RealTime,
         TimingMethodOption::GameTime,
       ]),
+      history: None,
+      ghost: None,
+      practice_start: 0,
+      practice_end: 0,
+      metadata: None,
+      splits_format: None,
     }
   }
 
+  /// goes back to practicing the first segment, used when the run changes
+  pub fn reset_practice_range(&mut self) {
+    self.practice_start = 0;
+    self.practice_end = 0;
+  }
+
+  /// updates the comparison selector from the run's comparisons
+  pub fn refresh_comparisons(&mut self, run: &Run) {
+    self.comparison_state =
+      combo_box::State::new(run.comparisons().map(|f| f.to_string()).collect());
+  }
+
   pub fn update(app: &mut App, message: MenuMessage) -> Result<Task<AppMessage>> {
     match message {
       MenuMessage::ToggleMenu => {
         app.menu.opened = !app.menu.opened;
+        app.menu.screen = MenuScreen::Main;
+        app.menu.ghost = None;
+        Ok(Task::none())
+      }
+      MenuMessage::OpenScreen(screen) => {
+        app.menu.history = match screen {
+          MenuScreen::History => {
+            let timer = app
+              .timer
+              .read()
+              .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+            Some(History::from_timer(&timer))
+          }
+          _ => None,
+        };
+        if !matches!(screen, MenuScreen::Ghost) {
+          app.menu.ghost = None;
+        }
+        app.menu.metadata = match screen {
+          MenuScreen::Metadata => {
+            let timer = app
+              .timer
+              .read()
+              .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+            Some(MetadataEditor::from_run(timer.run()))
+          }
+          _ => None,
+        };
+        app.menu.screen = screen;
         Ok(Task::none())
       }
       MenuMessage::ToggleHotkeys => {
@@ -100,10 +224,8 @@ impl Menu {
         }
 
         if !app.hotkeys_on {
-          for (action, hotkey) in &app.layout.hotkeys {
-            app
-              .hotkeys
-              .insert(app.hotkey_manager.register(hotkey.clone())?, action.clone());
+          for timer_index in 0..=app.extra_timers.len() {
+            app.register_timer_hotkeys(timer_index)?;
           }
         }
 
@@ -114,7 +236,7 @@ impl Menu {
       MenuMessage::LoadSplitsOpenPicker => {
         let future = Task::future(
           rfd::AsyncFileDialog::new()
-            .add_filter("Compatible Splits", &["lss"])
+            .add_filter("Compatible Splits", SPLITS_EXTENSIONS)
             .pick_file(),
         )
         .then(|handle| match handle {
@@ -132,13 +254,19 @@ impl Menu {
         let parsed_run = parser::parse_and_fix(&source, Some(p))?;
         let game_name = parsed_run.run.game_name().to_string();
         let category_name = parsed_run.run.category_name().to_string();
-        let timer = Timer::new(parsed_run.run)?;
-        app.repository.update_from_splits(timer.run())?;
-        app.menu.comparison_state =
-          combo_box::State::new(timer.run().comparisons().map(|f| f.to_string()).collect());
-        app.timer = timer.into_shared();
-        app.autosplitter = Runtime::new(app.timer.clone());
-        info!("loaded splits: {} - {}", game_name, category_name);
+        let format = parsed_run.kind.to_string();
+        if !app.confirm_replacing_splits()? {
+          return Ok(Task::none());
+        }
+        app.marathon = None;
+        app.load_run(parsed_run.run, Some(PathBuf::from(&path)))?;
+        info!(
+          "loaded splits: {} - {} ({})",
+          game_name, category_name, format
+        );
+        app.menu.splits_format = Some(format);
+        app.splits_edited = false;
+        app.watcher.splits = Some(WatchedFile::new(path));
         Ok(Task::none())
       }
       MenuMessage::SaveSplitsOpenPicker => {
@@ -177,12 +305,15 @@ impl Menu {
         Ok(future)
       }
       MenuMessage::LoadLayout(path) => {
-        let toml_string = read_to_string(path)?;
+        let toml_string = read_to_string(&path)?;
+        save_states(&app.lua_context.lua)
+          .unwrap_or_else(|err| error!("couldn't save component states: {}", err));
         let new_layout = Layout::load(
           &mut app.repository,
           &app.components,
           &app.lua_context.lua,
           toml_string,
+          Some(Path::new(&path)),
         )?;
         let width = new_layout.width;
         let height = new_layout.height;
@@ -191,6 +322,7 @@ impl Menu {
           app.hotkey_manager.unregister(id)?;
         }
         app.hotkeys_on = false;
+        app.watcher.layout = Some(WatchedFile::new(path));
         info!(
           "loaded layout: {} by {}",
           app.layout.name, app.layout.author
@@ -214,6 +346,7 @@ impl Menu {
       }
       MenuMessage::SaveLayout(path) => {
         app.layout.save(&path)?;
+        app.watcher.layout = Some(WatchedFile::new(path));
         info!("saved layout");
         Ok(Task::none())
       }
@@ -240,6 +373,59 @@ impl Menu {
         info!("loaded autosplitter");
         Ok(Task::none())
       }
+      MenuMessage::ExportStatisticsJsonOpenPicker => {
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .add_filter("JSON", &["json"])
+            .save_file(),
+        )
+        .then(|handle| match handle {
+          Some(handle) => {
+            let file_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::ExportStatisticsJson(
+              file_path,
+            )))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::ExportStatisticsJson(path) => {
+        let timer = app
+          .timer
+          .read()
+          .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+        export_statistics_json(timer.run(), timer.current_timing_method(), Path::new(&path))?;
+        info!("exported statistics");
+        Ok(Task::none())
+      }
+      MenuMessage::ExportStatisticsCsvOpenPicker => {
+        // the CSV export is one file per table, written in the picked folder
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .set_title("Folder to export the CSV statistics to")
+            .pick_folder(),
+        )
+        .then(|handle| match handle {
+          Some(handle) => {
+            let folder_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::ExportStatisticsCsv(
+              folder_path,
+            )))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::ExportStatisticsCsv(path) => {
+        let timer = app
+          .timer
+          .read()
+          .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+        export_statistics_csv(timer.run(), timer.current_timing_method(), Path::new(&path))?;
+        info!("exported statistics");
+        Ok(Task::none())
+      }
       MenuMessage::ChangeComparison(comp) => {
         if let Ok(mut timer) = app.timer.write() {
           timer
@@ -254,6 +440,280 @@ impl Menu {
         }
         Ok(Task::none())
       }
+      MenuMessage::ChangeMaxRefreshRate(rate) => {
+        app.layout.max_refresh_rate = rate;
+        Ok(Task::none())
+      }
+      MenuMessage::ToggleComparisonGenerator(name) => {
+        app.edit_run(|run| toggle_generator(run, &name))?;
+        Ok(Task::none())
+      }
+      MenuMessage::ToggleLuaComparison(name) => {
+        let comparison = app
+          .lua_comparisons
+          .iter()
+          .find(|c| c.name == name)
+          .ok_or(anyhow::Error::msg("couldn't find lua comparison"))?
+          .clone();
+        let lua = app.lua_context.lua.clone();
+        app.edit_run(|run| comparison.toggle(&lua, run))?;
+        Ok(Task::none())
+      }
+      MenuMessage::ImportGhostOpenPicker => {
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .add_filter("Compatible Splits", SPLITS_EXTENSIONS)
+            .add_filter("All Files", &["*"])
+            .pick_file(),
+        )
+        .then(|handle| match handle {
+          Some(handle) => {
+            let file_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::ImportGhost(file_path)))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::ImportGhost(path) => {
+        let p = Path::new(&path);
+        let source = fs::read(p)?;
+        let parsed_run = parser::parse_and_fix(&source, Some(p))?;
+        let timer = app
+          .timer
+          .read()
+          .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+        app.menu.ghost = Some(GhostImport::new(timer.run(), parsed_run.run, p));
+        app.menu.screen = MenuScreen::Ghost;
+        Ok(Task::none())
+      }
+      MenuMessage::ChangeGhostName(name) => {
+        if let Some(ghost) = &mut app.menu.ghost {
+          ghost.name = name;
+        }
+        Ok(Task::none())
+      }
+      MenuMessage::ChangeGhostSegment(segment, option) => {
+        if let Some(ghost) = &mut app.menu.ghost {
+          if let Some(index) = ghost.mapping.get_mut(segment) {
+            *index = option.index;
+          }
+        }
+        Ok(Task::none())
+      }
+      MenuMessage::ConfirmGhostImport => {
+        let ghost = app
+          .menu
+          .ghost
+          .take()
+          .ok_or(anyhow::Error::msg("no ghost splits to import"))?;
+        if let Err(e) =
+          app.edit_run(|run| import_ghost(run, &ghost.ghost, &ghost.name, &ghost.mapping))
+        {
+          app.menu.ghost = Some(ghost);
+          return Err(e);
+        }
+        info!("imported ghost splits as comparison: {}", ghost.name);
+        app.menu.screen = MenuScreen::Comparisons;
+        Ok(Task::none())
+      }
+      MenuMessage::ChangePracticeStart(index) => {
+        app.menu.practice_start = index;
+        app.menu.practice_end = app.menu.practice_end.max(index);
+        Ok(Task::none())
+      }
+      MenuMessage::ChangePracticeEnd(index) => {
+        app.menu.practice_end = index;
+        app.menu.practice_start = app.menu.practice_start.min(index);
+        Ok(Task::none())
+      }
+      MenuMessage::StartPractice => {
+        let timer = app
+          .timer
+          .read()
+          .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+        if timer.current_phase() != TimerPhase::NotRunning {
+          return Err(anyhow::Error::msg(
+            "can't start practicing while an attempt is going on",
+          ));
+        }
+        app.practice = Some(Practice::new(
+          timer.run(),
+          app.splits_path.clone(),
+          app.menu.practice_start,
+          app.menu.practice_end,
+          timer.current_timing_method(),
+        )?);
+        invalidate_value_cache(&app.lua_context.lua);
+        info!(
+          "started practicing segments {} to {}",
+          app.menu.practice_start + 1,
+          app.menu.practice_end + 1
+        );
+        Ok(Task::none())
+      }
+      MenuMessage::StopPractice => {
+        if let Some(mut practice) = app.practice.take() {
+          practice.handle_hotkey(HotkeyAction::ResetTimer)?;
+        }
+        invalidate_value_cache(&app.lua_context.lua);
+        info!("stopped practicing");
+        Ok(Task::none())
+      }
+      MenuMessage::LoadMarathonOpenPicker => {
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .add_filter("YAST Marathon", &["yasm"])
+            .pick_file(),
+        )
+        .then(|handle| match handle {
+          Some(handle) => {
+            let file_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::LoadMarathon(
+              file_path,
+            )))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::LoadMarathon(path) => {
+        let marathon = Marathon::load(Path::new(&path))?;
+        if !app.confirm_replacing_splits()? {
+          return Ok(Task::none());
+        }
+        app.load_run(
+          marathon.runs[0].clone(),
+          Some(marathon.current_path().to_path_buf()),
+        )?;
+        info!(
+          "loaded marathon: {} ({} categories)",
+          marathon.name,
+          marathon.runs.len()
+        );
+        app.marathon = Some(marathon);
+        app.splits_edited = false;
+        app.watcher.splits = None;
+        Ok(Task::none())
+      }
+      MenuMessage::SaveMarathon => {
+        if let Some(marathon) = &mut app.marathon {
+          let timer = app
+            .timer
+            .read()
+            .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+          marathon.save_all(timer.run())?;
+          app.splits_edited = false;
+          info!("saved marathon splits");
+        }
+        Ok(Task::none())
+      }
+      MenuMessage::StopMarathon => {
+        // finished categories only live in the marathon, so they're offered for saving first
+        if !app.confirm_discarding_timer(0)? {
+          return Ok(Task::none());
+        }
+        app.marathon = None;
+        info!("stopped marathon");
+        Ok(Task::none())
+      }
+      MenuMessage::AddTimerOpenPicker => {
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .add_filter("Compatible Splits", SPLITS_EXTENSIONS)
+            .pick_file(),
+        )
+        .then(|handle| match handle {
+          Some(handle) => {
+            let file_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::AddTimer(file_path)))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::AddTimer(path) => {
+        let p = Path::new(&path);
+        let source = fs::read(p)?;
+        let parsed_run = parser::parse_and_fix(&source, Some(p))?;
+        app.extra_timers.push(ExtraTimer::new(parsed_run.run)?);
+        if app.hotkeys_on {
+          app.register_timer_hotkeys(app.extra_timers.len())?;
+        }
+        info!("added timer {}", app.extra_timers.len() + 1);
+        Ok(Task::none())
+      }
+      MenuMessage::RemoveTimer(index) => {
+        let extra_index = index
+          .checked_sub(1)
+          .filter(|i| *i < app.extra_timers.len())
+          .ok_or(anyhow::Error::msg(format!(
+            "couldn't find timer {}",
+            index + 1
+          )))?;
+        if !app.confirm_discarding_timer(index)? {
+          return Ok(Task::none());
+        }
+        app.extra_timers.remove(extra_index);
+
+        // the following timers move down, so their hotkeys and components follow them
+        for (id, (timer_index, action)) in app.hotkeys.drain().collect::<Vec<_>>() {
+          if timer_index == index {
+            app.hotkey_manager.unregister(id)?;
+          } else if timer_index > index {
+            app.hotkeys.insert(id, (timer_index - 1, action));
+          } else {
+            app.hotkeys.insert(id, (timer_index, action));
+          }
+        }
+        app.layout.remove_timer(index);
+        invalidate_memos(&app.lua_context.lua);
+        invalidate_value_cache(&app.lua_context.lua);
+        info!("removed timer {}", index + 1);
+        Ok(Task::none())
+      }
+      MenuMessage::SaveTimerOpenPicker(index) => {
+        let future = Task::future(
+          rfd::AsyncFileDialog::new()
+            .add_filter("LiveSplit Splits", &["lss"])
+            .save_file(),
+        )
+        .then(move |handle| match handle {
+          Some(handle) => {
+            let file_path = handle.path().to_string_lossy().to_string();
+            Task::done(AppMessage::MenuMessage(MenuMessage::SaveTimer(
+              index, file_path,
+            )))
+          }
+          None => Task::none(),
+        });
+        Ok(future)
+      }
+      MenuMessage::Metadata(message) => {
+        if let Some(metadata) = &mut app.menu.metadata {
+          metadata.update(message);
+        }
+        Ok(Task::none())
+      }
+      MenuMessage::ApplyMetadata => {
+        let metadata = app
+          .menu
+          .metadata
+          .take()
+          .ok_or(anyhow::Error::msg("no metadata to apply"))?;
+        let result = app.edit_run(|run| metadata.apply(run));
+        app.menu.metadata = Some(metadata);
+        result?;
+        info!("applied run metadata");
+        Ok(Task::none())
+      }
+      MenuMessage::SaveTimer(index, path) => {
+        let extra_timer = app.extra_timer_mut(index)?;
+        save_splits_of(&extra_timer.timer, path)?;
+        extra_timer.edited = false;
+        info!("saved splits of timer {}", index + 1);
+        Ok(Task::none())
+      }
     }
   }
 
@@ -267,6 +727,199 @@ impl Menu {
         .into(),
     );
 
+    match app.menu.screen {
+      MenuScreen::Main => Self::view_main(app, &mut children),
+      MenuScreen::History => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Main,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        if let Some(history) = &app.menu.history {
+          children.push(history.view());
+        }
+      }
+      MenuScreen::Comparisons => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Main,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        Self::view_comparisons(app, &mut children);
+      }
+      MenuScreen::Ghost => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Comparisons,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        if let Some(ghost) = &app.menu.ghost {
+          children.push(ghost.view());
+        }
+      }
+      MenuScreen::Timers => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Main,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        Self::view_timers(app, &mut children);
+      }
+      MenuScreen::Metadata => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Main,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        if let Some(metadata) = &app.menu.metadata {
+          children.push(metadata.view());
+        }
+      }
+      MenuScreen::Practice => {
+        children.push(
+          button("Back")
+            .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+              MenuScreen::Main,
+            )))
+            .style(button::secondary)
+            .into(),
+        );
+        children.push(practice::view(
+          app,
+          app.menu.practice_start,
+          app.menu.practice_end,
+        ));
+      }
+    }
+
+    let content = stack(vec![
+      container(space().width(Length::Fill).height(Length::Fill))
+        .style(|_| container::Style {
+          background: Some(Background::Color(Color::from_rgba(0., 0., 0., 0.5))),
+          ..Default::default()
+        })
+        .into(),
+      column(children)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(10.)
+        .spacing(5.)
+        .align_x(Horizontal::Center)
+        .into(),
+    ]);
+
+    let opaque = opaque(content).into();
+
+    opaque
+  }
+
+  fn view_timers<'a>(app: &'a App, children: &mut Vec<Element<'a, AppMessage>>) {
+    if let Ok(timer) = app.timer.read() {
+      children.push(
+        text(format!(
+          "Timer 1: {} - {}",
+          timer.run().game_name(),
+          timer.run().category_name()
+        ))
+        .into(),
+      );
+    }
+
+    for (i, extra_timer) in app.extra_timers.iter().enumerate() {
+      let index = i + 1;
+      let Ok(timer) = extra_timer.timer.read() else {
+        continue;
+      };
+      children.push(
+        row(vec![
+          text(format!(
+            "Timer {}: {} - {}",
+            index + 1,
+            timer.run().game_name(),
+            timer.run().category_name()
+          ))
+          .into(),
+          button("Save")
+            .on_press(AppMessage::MenuMessage(MenuMessage::SaveTimerOpenPicker(
+              index,
+            )))
+            .style(button::secondary)
+            .into(),
+          button("Remove")
+            .on_press(AppMessage::MenuMessage(MenuMessage::RemoveTimer(index)))
+            .style(button::danger)
+            .into(),
+        ])
+        .spacing(5.)
+        .align_y(Vertical::Center)
+        .into(),
+      );
+    }
+
+    children.push(
+      button("Add Timer")
+        .on_press(AppMessage::MenuMessage(MenuMessage::AddTimerOpenPicker))
+        .into(),
+    );
+  }
+
+  fn view_comparisons<'a>(app: &'a App, children: &mut Vec<Element<'a, AppMessage>>) {
+    if let Ok(timer) = app.timer.read() {
+      children.push(text("Generators").size(16.).into());
+      for name in OPTIONAL_GENERATORS {
+        let mut generator_button = button(name).on_press(AppMessage::MenuMessage(
+          MenuMessage::ToggleComparisonGenerator(name.to_string()),
+        ));
+        if has_generator(timer.run(), name) {
+          generator_button = generator_button.style(button::success);
+        } else {
+          generator_button = generator_button.style(button::secondary);
+        }
+        children.push(generator_button.into());
+      }
+
+      children.push(text("Lua Comparisons").size(16.).into());
+      if app.lua_comparisons.is_empty() {
+        children.push(text("no lua comparisons found").size(10.).into());
+      }
+      for comparison in &app.lua_comparisons {
+        let mut comparison_button = button(text(comparison.name.clone())).on_press(
+          AppMessage::MenuMessage(MenuMessage::ToggleLuaComparison(comparison.name.clone())),
+        );
+        if comparison.is_enabled(timer.run()) {
+          comparison_button = comparison_button.style(button::success);
+        } else {
+          comparison_button = comparison_button.style(button::secondary);
+        }
+        children.push(comparison_button.into());
+      }
+
+      children.push(text("Ghost Splits").size(16.).into());
+      children.push(
+        button("Import Splits as Comparison")
+          .on_press(AppMessage::MenuMessage(MenuMessage::ImportGhostOpenPicker))
+          .style(button::secondary)
+          .into(),
+      );
+    }
+  }
+
+  fn view_main<'a>(app: &'a App, children: &mut Vec<Element<'a, AppMessage>>) {
     children.push(
       row(vec![
         button("Load Splits")
@@ -281,6 +934,10 @@ impl Menu {
       .into(),
     );
 
+    if let Some(format) = &app.menu.splits_format {
+      children.push(text(format!("Splits loaded from {}", format)).into());
+    }
+
     children.push(
       row(vec![
         button("Load Layout")
@@ -295,6 +952,27 @@ impl Menu {
       .into(),
     );
 
+    let mut marathon_row = vec![
+      button("Load Marathon")
+        .on_press(AppMessage::MenuMessage(MenuMessage::LoadMarathonOpenPicker))
+        .into(),
+    ];
+    if app.marathon.is_some() {
+      marathon_row.push(
+        button("Save Marathon")
+          .on_press(AppMessage::MenuMessage(MenuMessage::SaveMarathon))
+          .style(button::secondary)
+          .into(),
+      );
+      marathon_row.push(
+        button("Stop Marathon")
+          .on_press(AppMessage::MenuMessage(MenuMessage::StopMarathon))
+          .style(button::danger)
+          .into(),
+      );
+    }
+    children.push(row(marathon_row).spacing(5.).into());
+
     children.push(
       button("Load Autosplitter")
         .on_press(AppMessage::MenuMessage(
@@ -313,10 +991,36 @@ impl Menu {
     }
     children.push(hotkey_button.into());
 
-    for (action, hotkey) in &app.layout.hotkeys {
-      children.push(text(format!("{:?}: {}", action, hotkey)).size(10.).into());
+    for timer_index in 0..=app.extra_timers.len() {
+      if let Some(hotkeys) = app.layout.timer_hotkeys(timer_index) {
+        for (action, hotkey) in hotkeys {
+          let label = match timer_index {
+            0 => format!("{:?}: {}", action, hotkey),
+            _ => format!("Timer {} {:?}: {}", timer_index + 1, action, hotkey),
+          };
+          children.push(text(label).size(10.).into());
+        }
+      }
     }
 
+    children.push(
+      row(vec![
+        text(format!(
+          "Max Refresh Rate: {} Hz",
+          app.layout.max_refresh_rate
+        ))
+        .into(),
+        slider(10.0..=240.0, app.layout.max_refresh_rate, |r| {
+          AppMessage::MenuMessage(MenuMessage::ChangeMaxRefreshRate(r))
+        })
+        .step(5.)
+        .into(),
+      ])
+      .spacing(5.)
+      .align_y(Vertical::Center)
+      .into(),
+    );
+
     if let Ok(timer) = app.timer.read() {
       children.push(
         combo_box(
@@ -339,6 +1043,68 @@ impl Menu {
       );
     }
 
+    children.push(
+      button("Manage Comparisons")
+        .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+          MenuScreen::Comparisons,
+        )))
+        .style(button::secondary)
+        .into(),
+    );
+
+    children.push(
+      button("Edit Run Metadata")
+        .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+          MenuScreen::Metadata,
+        )))
+        .style(button::secondary)
+        .into(),
+    );
+
+    children.push(
+      button("Timers")
+        .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+          MenuScreen::Timers,
+        )))
+        .style(button::secondary)
+        .into(),
+    );
+
+    let mut practice_button = button("Practice Mode").on_press(AppMessage::MenuMessage(
+      MenuMessage::OpenScreen(MenuScreen::Practice),
+    ));
+    if app.practice.is_some() {
+      practice_button = practice_button.style(button::success);
+    } else {
+      practice_button = practice_button.style(button::secondary);
+    }
+    children.push(practice_button.into());
+
+    children.push(
+      row(vec![
+        button("Attempt History")
+          .on_press(AppMessage::MenuMessage(MenuMessage::OpenScreen(
+            MenuScreen::History,
+          )))
+          .style(button::secondary)
+          .into(),
+        button("Export Statistics (JSON)")
+          .on_press(AppMessage::MenuMessage(
+            MenuMessage::ExportStatisticsJsonOpenPicker,
+          ))
+          .style(button::secondary)
+          .into(),
+        button("Export Statistics (CSV)")
+          .on_press(AppMessage::MenuMessage(
+            MenuMessage::ExportStatisticsCsvOpenPicker,
+          ))
+          .style(button::secondary)
+          .into(),
+      ])
+      .spacing(5.)
+      .into(),
+    );
+
     children.push(space().width(Length::Fill).height(Length::Fill).into());
     children.push(
       image(app.menu.sapho_handle.clone())
@@ -353,25 +1119,5 @@ impl Menu {
         .size(10.)
         .into(),
     );
-
-    let content = stack(vec![
-      container(space().width(Length::Fill).height(Length::Fill))
-        .style(|_| container::Style {
-          background: Some(Background::Color(Color::from_rgba(0., 0., 0., 0.5))),
-          ..Default::default()
-        })
-        .into(),
-      column(children)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .padding(10.)
-        .spacing(5.)
-        .align_x(Horizontal::Center)
-        .into(),
-    ]);
-
-    let opaque = opaque(content).into();
-
-    opaque
   }
 }
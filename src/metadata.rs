@@ -0,0 +1,249 @@
+//! run metadata editor
+
+use anyhow::Result;
+use iced::{
+  Element, Length,
+  alignment::Vertical,
+  widget::{button, checkbox, column, row, scrollable, text, text_input},
+};
+use livesplit_core::{Run, TimeSpan};
+
+use crate::{AppMessage, menu::MenuMessage};
+
+/// largest start offset accepted, in seconds, either way
+const MAX_OFFSET: f64 = 24. * 60. * 60.;
+
+#[derive(Clone, Debug)]
+pub enum MetadataMessage {
+  ChangeGameName(String),
+  ChangeCategoryName(String),
+  ChangePlatform(String),
+  ChangeRegion(String),
+  ChangeOffset(String),
+  ToggleEmulator(bool),
+  AddSpeedrunComVariable,
+  ChangeSpeedrunComVariable(usize, String, String),
+  RemoveSpeedrunComVariable(usize),
+  AddCustomVariable,
+  ChangeCustomVariable(usize, String, String),
+  RemoveCustomVariable(usize),
+}
+
+/// editable copy of the run's metadata, applied to the run on demand
+pub struct MetadataEditor {
+  game_name: String,
+  category_name: String,
+  platform: String,
+  region: String,
+  uses_emulator: bool,
+  /// start offset in seconds, negative for a delay before the first split
+  offset: String,
+  speedrun_com_variables: Vec<(String, String)>,
+  /// only permanent custom variables are edited, the temporary ones belong to autosplitters
+  custom_variables: Vec<(String, String)>,
+}
+
+fn message(m: MetadataMessage) -> AppMessage {
+  AppMessage::MenuMessage(MenuMessage::Metadata(m))
+}
+
+fn field<'a>(
+  label: &'static str,
+  value: &str,
+  on_change: fn(String) -> MetadataMessage,
+) -> Element<'a, AppMessage> {
+  row(vec![
+    text(label).width(Length::Fixed(80.)).into(),
+    text_input(label, value)
+      .on_input(move |s| message(on_change(s)))
+      .into(),
+  ])
+  .spacing(5.)
+  .align_y(Vertical::Center)
+  .into()
+}
+
+impl MetadataEditor {
+  pub fn from_run(run: &Run) -> Self {
+    let metadata = run.metadata();
+    Self {
+      game_name: run.game_name().to_string(),
+      category_name: run.category_name().to_string(),
+      platform: metadata.platform_name().to_string(),
+      region: metadata.region_name().to_string(),
+      uses_emulator: metadata.uses_emulator(),
+      offset: format!("{}", run.offset().total_seconds()),
+      speedrun_com_variables: metadata
+        .speedrun_com_variables()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect(),
+      custom_variables: metadata
+        .custom_variables()
+        .filter(|(_, v)| v.is_permanent)
+        .map(|(k, v)| (k.to_string(), v.value.clone()))
+        .collect(),
+    }
+  }
+
+  pub fn update(&mut self, message: MetadataMessage) {
+    match message {
+      MetadataMessage::ChangeGameName(s) => self.game_name = s,
+      MetadataMessage::ChangeCategoryName(s) => self.category_name = s,
+      MetadataMessage::ChangePlatform(s) => self.platform = s,
+      MetadataMessage::ChangeRegion(s) => self.region = s,
+      MetadataMessage::ToggleEmulator(b) => self.uses_emulator = b,
+      MetadataMessage::ChangeOffset(s) => self.offset = s,
+      MetadataMessage::AddSpeedrunComVariable => self
+        .speedrun_com_variables
+        .push((String::new(), String::new())),
+      MetadataMessage::ChangeSpeedrunComVariable(i, name, value) => {
+        if let Some(variable) = self.speedrun_com_variables.get_mut(i) {
+          *variable = (name, value);
+        }
+      }
+      MetadataMessage::RemoveSpeedrunComVariable(i) => {
+        if i < self.speedrun_com_variables.len() {
+          self.speedrun_com_variables.remove(i);
+        }
+      }
+      MetadataMessage::AddCustomVariable => {
+        self.custom_variables.push((String::new(), String::new()))
+      }
+      MetadataMessage::ChangeCustomVariable(i, name, value) => {
+        if let Some(variable) = self.custom_variables.get_mut(i) {
+          *variable = (name, value);
+        }
+      }
+      MetadataMessage::RemoveCustomVariable(i) => {
+        if i < self.custom_variables.len() {
+          self.custom_variables.remove(i);
+        }
+      }
+    }
+  }
+
+  /// writes the edited metadata in the run
+  pub fn apply(&self, run: &mut Run) -> Result<()> {
+    let offset = self
+      .offset
+      .trim()
+      .parse::<f64>()
+      .ok()
+      .filter(|offset| offset.is_finite() && offset.abs() <= MAX_OFFSET)
+      .ok_or(anyhow::Error::msg(
+        "invalid start offset, expected a number of seconds of at most a day",
+      ))?;
+
+    run.set_game_name(self.game_name.as_str());
+    run.set_category_name(self.category_name.as_str());
+    run.set_offset(TimeSpan::from_seconds(offset));
+
+    let metadata = run.metadata_mut();
+    metadata.set_platform_name(self.platform.as_str());
+    metadata.set_region_name(self.region.as_str());
+    metadata.set_emulator_usage(self.uses_emulator);
+
+    metadata.clear_speedrun_com_variables();
+    for (name, value) in &self.speedrun_com_variables {
+      if !name.is_empty() {
+        metadata.set_speedrun_com_variable(name.as_str(), value.as_str());
+      }
+    }
+
+    let removed = metadata
+      .custom_variables()
+      .filter(|(_, v)| v.is_permanent)
+      .map(|(k, _)| k.to_string())
+      .collect::<Vec<String>>();
+    for name in removed {
+      metadata.remove_custom_variable(&name);
+    }
+    for (name, value) in &self.custom_variables {
+      if !name.is_empty() {
+        let variable = metadata.custom_variable_mut(name.as_str());
+        variable.value = value.clone();
+        variable.is_permanent = true;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn variables_view<'a>(
+    title: &'a str,
+    variables: &'a [(String, String)],
+    on_change: fn(usize, String, String) -> MetadataMessage,
+    on_remove: fn(usize) -> MetadataMessage,
+    on_add: MetadataMessage,
+  ) -> Element<'a, AppMessage> {
+    let mut children: Vec<Element<'a, AppMessage>> = vec![text(title).size(16.).into()];
+    for (i, (name, value)) in variables.iter().enumerate() {
+      let value_for_name = value.clone();
+      let name_for_value = name.clone();
+      children.push(
+        row(vec![
+          text_input("Name", name)
+            .on_input(move |s| message(on_change(i, s, value_for_name.clone())))
+            .into(),
+          text_input("Value", value)
+            .on_input(move |s| message(on_change(i, name_for_value.clone(), s)))
+            .into(),
+          button("Remove")
+            .on_press(message(on_remove(i)))
+            .style(button::danger)
+            .into(),
+        ])
+        .spacing(5.)
+        .align_y(Vertical::Center)
+        .into(),
+      );
+    }
+    children.push(
+      button("Add Variable")
+        .on_press(message(on_add))
+        .style(button::secondary)
+        .into(),
+    );
+    column(children).spacing(5.).into()
+  }
+
+  pub fn view(&self) -> Element<'_, AppMessage> {
+    let children: Vec<Element<'_, AppMessage>> = vec![
+      field("Game", &self.game_name, MetadataMessage::ChangeGameName),
+      field(
+        "Category",
+        &self.category_name,
+        MetadataMessage::ChangeCategoryName,
+      ),
+      field("Platform", &self.platform, MetadataMessage::ChangePlatform),
+      field("Region", &self.region, MetadataMessage::ChangeRegion),
+      field("Offset (s)", &self.offset, MetadataMessage::ChangeOffset),
+      checkbox(self.uses_emulator)
+        .label("Uses Emulator")
+        .on_toggle(|b| message(MetadataMessage::ToggleEmulator(b)))
+        .into(),
+      Self::variables_view(
+        "Speedrun.com Variables",
+        &self.speedrun_com_variables,
+        MetadataMessage::ChangeSpeedrunComVariable,
+        MetadataMessage::RemoveSpeedrunComVariable,
+        MetadataMessage::AddSpeedrunComVariable,
+      ),
+      Self::variables_view(
+        "Custom Variables",
+        &self.custom_variables,
+        MetadataMessage::ChangeCustomVariable,
+        MetadataMessage::RemoveCustomVariable,
+        MetadataMessage::AddCustomVariable,
+      ),
+      button("Apply")
+        .on_press(AppMessage::MenuMessage(MenuMessage::ApplyMetadata))
+        .style(button::success)
+        .into(),
+    ];
+
+    scrollable(column(children).spacing(5.).width(Length::Fill))
+      .height(Length::Fill)
+      .into()
+  }
+}
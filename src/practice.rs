@@ -0,0 +1,141 @@
+//! practice mode screen
+
+use std::fmt::Display;
+
+use iced::{
+  Element, Length,
+  widget::{button, column, pick_list, row, scrollable, text},
+};
+
+use crate::{App, AppMessage, history::format_time, menu::MenuMessage};
+
+/// segment of the run that can be picked as a practice bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct PracticeSegmentOption {
+  pub index: usize,
+  name: String,
+}
+
+impl Display for PracticeSegmentOption {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}. {}", self.index + 1, self.name)
+  }
+}
+
+pub fn view<'a>(app: &'a App, start: usize, end: usize) -> Element<'a, AppMessage> {
+  let mut children: Vec<Element<'a, AppMessage>> = Vec::new();
+
+  let Ok(timer) = app.timer.read() else {
+    return column(children).into();
+  };
+
+  if let Some(practice) = &app.practice {
+    children.push(
+      text(format!(
+        "Practicing {} to {}",
+        practice.start + 1,
+        practice.end + 1
+      ))
+      .size(16.)
+      .into(),
+    );
+    children.push(
+      button("Stop Practice")
+        .on_press(AppMessage::MenuMessage(MenuMessage::StopPractice))
+        .style(button::danger)
+        .into(),
+    );
+
+    let timing_method = timer.current_timing_method();
+    let range = practice.range_statistics(timing_method);
+    children.push(
+      text(format!(
+        "{} attempts, {} finished, best {}, average {}",
+        practice.range_attempts().count(),
+        range.count,
+        format_time(range.best),
+        format_time(range.average)
+      ))
+      .size(12.)
+      .into(),
+    );
+
+    let mut segments: Vec<Element<'a, AppMessage>> = vec![
+      row(vec![
+        text("Segment").width(Length::Fill).into(),
+        text("Count").width(Length::Fixed(50.)).into(),
+        text("Best").width(Length::Fixed(70.)).into(),
+        text("Average").width(Length::Fixed(70.)).into(),
+      ])
+      .into(),
+    ];
+    for i in practice.start..=practice.end {
+      let statistics = practice.segment_statistics(i, timing_method);
+      segments.push(
+        row(vec![
+          text(
+            timer
+              .run()
+              .segments()
+              .get(i)
+              .map(|s| s.name().to_string())
+              .unwrap_or_default(),
+          )
+          .width(Length::Fill)
+          .into(),
+          text(statistics.count.to_string())
+            .width(Length::Fixed(50.))
+            .into(),
+          text(format_time(statistics.best))
+            .width(Length::Fixed(70.))
+            .into(),
+          text(format_time(statistics.average))
+            .width(Length::Fixed(70.))
+            .into(),
+        ])
+        .into(),
+      );
+    }
+    children.push(
+      scrollable(column(segments).spacing(2.).width(Length::Fill))
+        .height(Length::Fill)
+        .into(),
+    );
+  } else {
+    let options = timer
+      .run()
+      .segments()
+      .iter()
+      .enumerate()
+      .map(|(index, s)| PracticeSegmentOption {
+        index,
+        name: s.name().to_string(),
+      })
+      .collect::<Vec<PracticeSegmentOption>>();
+    let selected_start = options.get(start).cloned();
+    let selected_end = options.get(end).cloned();
+
+    children.push(text("Start Segment").size(12.).into());
+    children.push(
+      pick_list(options.clone(), selected_start, |o| {
+        AppMessage::MenuMessage(MenuMessage::ChangePracticeStart(o.index))
+      })
+      .into(),
+    );
+    children.push(text("End Segment").size(12.).into());
+    children.push(
+      pick_list(options, selected_end, |o| {
+        AppMessage::MenuMessage(MenuMessage::ChangePracticeEnd(o.index))
+      })
+      .into(),
+    );
+    children.push(
+      button("Start Practice")
+        .on_press(AppMessage::MenuMessage(MenuMessage::StartPractice))
+        .style(button::success)
+        .into(),
+    );
+  }
+
+  column(children).spacing(5.).width(Length::Fill).into()
+}
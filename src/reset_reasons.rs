@@ -0,0 +1,72 @@
+//! reset reason prompt, shown over the layout after a reset
+
+use anyhow::Result;
+use iced::{
+  Element, Length,
+  widget::{button, column, container, row, text},
+};
+
+use crate::{App, AppMessage};
+
+/// reset attempt waiting for its reason
+#[derive(Clone, Copy, Debug)]
+pub struct PendingReset {
+  pub attempt: i32,
+  /// index of the segment the attempt was reset in
+  pub segment: usize,
+}
+
+impl App {
+  /// tags a reset attempt with the reason at `reason` in the run's reason list, and saves it
+  ///
+  /// tags of runs without a splits file are saved along with the splits
+  pub fn tag_reset(&mut self, reset: PendingReset, reason: usize) -> Result<()> {
+    self
+      .reset_reasons
+      .tag(reset.attempt, reset.segment, reason)?;
+    if let Some(splits_path) = &self.splits_path {
+      self.reset_reasons.save(splits_path)?;
+    }
+    self.pending_reset = None;
+    info!(
+      "tagged attempt {} with reset reason {}",
+      reset.attempt, self.reset_reasons.reasons[reason]
+    );
+    Ok(())
+  }
+}
+
+pub fn prompt(app: &App) -> Element<'_, AppMessage> {
+  let mut buttons: Vec<Element<'_, AppMessage>> = app
+    .reset_reasons
+    .reasons
+    .iter()
+    .enumerate()
+    .map(|(i, reason)| {
+      button(text(reason.as_str()))
+        .on_press(AppMessage::TagReset(i))
+        .into()
+    })
+    .collect();
+  buttons.push(
+    button("Skip")
+      .on_press(AppMessage::DismissResetPrompt)
+      .style(button::secondary)
+      .into(),
+  );
+
+  container(
+    container(
+      column(vec![
+        text("Why did you reset?").into(),
+        row(buttons).spacing(5.).wrap().into(),
+      ])
+      .spacing(5.),
+    )
+    .padding(10.)
+    .width(Length::Fill)
+    .style(container::dark),
+  )
+  .align_bottom(Length::Fill)
+  .into()
+}
@@ -1,13 +1,22 @@
 use anyhow::Result;
 use handy_keys::HotkeyState;
 use iced::{Size, Task, window};
+use livesplit_core::TimerPhase;
 use rfd::{MessageButtons, MessageDialog, MessageDialogResult};
-use yast_core::layout::HotkeyAction;
+use yast_core::{
+  layout::{HotkeyAction, state::save_states},
+  lua::inject::invalidate_value_cache,
+};
 
 #[cfg(target_os = "windows")]
 use iced::keyboard;
 
-use crate::{App, AppMessage, menu::Menu};
+use crate::{
+  App, AppMessage,
+  menu::{Menu, MenuMessage},
+  reset_reasons::PendingReset,
+  save_splits_of,
+};
 
 impl App {
   pub fn update_handler(&mut self, message: AppMessage) -> Task<AppMessage> {
@@ -28,52 +37,184 @@ impl App {
     })
   }
 
-  /// common function for handling hotkeys
+  /// asks to save unsaved splits before they are replaced or the app is closed
   ///
-  /// used by windows-only calls and the regular handykeys callback
-  pub fn handle_hotkey(&mut self, action: HotkeyAction) -> Result<()> {
-    let mut timer = self
-      .timer
-      .write()
-      .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+  /// returns whether the splits can be replaced
+  pub fn confirm_replacing_splits(&mut self) -> Result<bool> {
+    self.confirm_discarding_timer(0)
+  }
 
-    match action {
-      HotkeyAction::StartOrSplitTimer => {
-        timer.split_or_start();
-      }
-      HotkeyAction::StartTimer => {
-        timer.start();
-      }
-      HotkeyAction::SplitTimer => {
-        timer.split();
-      }
-      HotkeyAction::ResetTimerWithoutSaving => {
-        timer.reset(false);
-      }
-      HotkeyAction::ResetTimer => {
-        self.splits_edited = true;
-        timer.reset(true);
+  /// asks to save the unsaved splits of every timer before the app is closed
+  ///
+  /// returns whether the app can be closed
+  pub fn confirm_closing(&mut self) -> Result<bool> {
+    for timer_index in 0..=self.extra_timers.len() {
+      if !self.confirm_discarding_timer(timer_index)? {
+        return Ok(false);
       }
-      HotkeyAction::SkipSplit => {
-        timer.skip_split();
+    }
+    Ok(true)
+  }
+
+  /// asks to save the unsaved splits of a timer, by timer index, before they are discarded
+  ///
+  /// returns whether the splits can be discarded
+  pub fn confirm_discarding_timer(&mut self, timer_index: usize) -> Result<bool> {
+    let edited = match timer_index {
+      0 => self.splits_edited,
+      _ => self.extra_timer_mut(timer_index)?.edited,
+    };
+    if !edited {
+      return Ok(true);
+    }
+
+    // finished categories only live in the marathon, so they're saved back to their own files
+    if timer_index == 0 && self.marathon.is_some() {
+      return self.confirm_discarding_marathon();
+    }
+
+    let description = match timer_index {
+      0 => "Splits haven't been saved. Would you like to save them?".to_string(),
+      _ => format!(
+        "Splits of timer {} haven't been saved. Would you like to save them?",
+        timer_index + 1
+      ),
+    };
+    let result = MessageDialog::new()
+      .set_title("Save Splits?")
+      .set_description(description)
+      .set_buttons(MessageButtons::YesNoCancel)
+      .show();
+
+    match result {
+      MessageDialogResult::No => Ok(true),
+      MessageDialogResult::Yes => {
+        let result = rfd::FileDialog::new()
+          .add_filter("LiveSplit Splits", &["lss"])
+          .save_file();
+        match result {
+          Some(path) => {
+            let path = path.to_string_lossy().to_string();
+            match timer_index {
+              0 => {
+                self.save_splits(path)?;
+              }
+              _ => {
+                let extra_timer = self.extra_timer_mut(timer_index)?;
+                save_splits_of(&extra_timer.timer, path)?;
+                extra_timer.edited = false;
+              }
+            }
+            Ok(true)
+          }
+          None => Ok(false),
+        }
       }
-      HotkeyAction::UndoSplit => {
-        timer.undo_split();
+      MessageDialogResult::Cancel => Ok(false),
+      _ => unreachable!(),
+    }
+  }
+
+  /// asks to save every category of the marathon before they are discarded
+  ///
+  /// returns whether the marathon splits can be discarded
+  fn confirm_discarding_marathon(&mut self) -> Result<bool> {
+    let result = MessageDialog::new()
+      .set_title("Save Marathon?")
+      .set_description(
+        "Marathon splits haven't been saved. Would you like to save every category to its splits file?",
+      )
+      .set_buttons(MessageButtons::YesNoCancel)
+      .show();
+
+    match result {
+      MessageDialogResult::No => Ok(true),
+      MessageDialogResult::Yes => {
+        if let Some(marathon) = &mut self.marathon {
+          let timer = self
+            .timer
+            .read()
+            .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+          marathon.save_all(timer.run())?;
+        }
+        self.splits_edited = false;
+        info!("saved marathon splits");
+        Ok(true)
       }
-      HotkeyAction::PauseTimer => {
-        timer.toggle_pause();
+      MessageDialogResult::Cancel => Ok(false),
+      _ => unreachable!(),
+    }
+  }
+
+  /// common function for handling hotkeys
+  ///
+  /// used by windows-only calls and the regular handykeys callback
+  pub fn handle_hotkey(&mut self, timer_index: usize, action: HotkeyAction) -> Result<()> {
+    if timer_index > 0 {
+      let extra_timer = self.extra_timer_mut(timer_index)?;
+      let mut timer = extra_timer
+        .timer
+        .write()
+        .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+      let resetting = action == HotkeyAction::ResetTimer || action.reset_reason().is_some();
+      if resetting {
+        extra_timer.edited = true;
       }
-      HotkeyAction::ToggleTimingMethod => {
-        timer.toggle_timing_method();
+      action.apply(&mut timer);
+      if resetting {
+        invalidate_value_cache(&self.lua_context.lua);
       }
-      HotkeyAction::NextComparison => {
-        timer.switch_to_next_comparison();
+      return Ok(());
+    }
+
+    if let Some(practice) = &mut self.practice {
+      return practice.handle_hotkey(action);
+    }
+
+    let mut history_changed = false;
+    let mut reset = None;
+
+    {
+      let mut timer = self
+        .timer
+        .write()
+        .map_err(|_| anyhow::Error::msg("couldn't access timer"))?;
+
+      // finished attempts have no reason to be tagged with
+      let segment = match timer.current_phase() {
+        TimerPhase::Running | TimerPhase::Paused => timer.current_split_index(),
+        _ => None,
+      };
+
+      let resetting = action == HotkeyAction::ResetTimer || action.reset_reason().is_some();
+      if resetting {
+        self.splits_edited = true;
+        history_changed = true;
       }
-      HotkeyAction::PreviousComparison => {
-        timer.switch_to_previous_comparison();
+      action.apply(&mut timer);
+
+      if resetting {
+        let attempt = timer.run().attempt_history().last().map(|a| a.index());
+        reset = segment
+          .zip(attempt)
+          .map(|(segment, attempt)| PendingReset { attempt, segment });
       }
     }
 
+    // the next category starts on the split ending the current one, so no time is lost until the next update
+    self.update_marathon()?;
+
+    if history_changed {
+      self.refresh_lua_comparisons()?;
+      invalidate_value_cache(&self.lua_context.lua);
+    }
+
+    match (reset.or(self.pending_reset), action.reset_reason()) {
+      (Some(reset), Some(reason)) => self.tag_reset(reset, reason)?,
+      (Some(reset), None) => self.pending_reset = Some(reset),
+      (None, _) => {}
+    }
+
     Ok(())
   }
 
@@ -84,9 +225,9 @@ impl App {
         Ok(Task::none())
       }
       AppMessage::Update => {
-        if let Some(event) = self.hotkey_manager.try_recv() {
+        while let Some(event) = self.hotkey_manager.try_recv() {
           if let HotkeyState::Pressed = event.state {
-            let hotkey = self
+            let (timer_index, action) = self
               .hotkeys
               .get(&event.id)
               .ok_or(anyhow::Error::msg(format!(
@@ -94,10 +235,48 @@ impl App {
                 &event.id
               )))?
               .clone();
-            self.handle_hotkey(hotkey)?;
+            self.handle_hotkey(timer_index, action)?;
+          }
+        }
+
+        self.update_marathon()?;
+
+        if self.pending_reset.is_some() {
+          let phase = self
+            .timer
+            .read()
+            .map_err(|_| anyhow::Error::msg("couldn't access timer"))?
+            .current_phase();
+          if phase != TimerPhase::NotRunning {
+            self.pending_reset = None;
           }
         }
 
+        self.check_watched_files()
+      }
+      AppMessage::FileDropped(path) => {
+        let extension = path
+          .extension()
+          .map(|e| e.to_string_lossy().to_lowercase())
+          .unwrap_or_default();
+        let file_path = path.to_string_lossy().to_string();
+        // anything that isn't a YAST file is left to livesplit-core's splits parser
+        let message = match extension.as_str() {
+          "yasl" => MenuMessage::LoadLayout(file_path),
+          "yasm" => MenuMessage::LoadMarathon(file_path),
+          "wasm" => MenuMessage::LoadAutosplitter(file_path),
+          _ => MenuMessage::LoadSplits(file_path),
+        };
+        Menu::update(self, message)
+      }
+      AppMessage::TagReset(reason) => {
+        if let Some(reset) = self.pending_reset {
+          self.tag_reset(reset, reason)?;
+        }
+        Ok(Task::none())
+      }
+      AppMessage::DismissResetPrompt => {
+        self.pending_reset = None;
         Ok(Task::none())
       }
       AppMessage::WindowResized((_id, size)) => {
@@ -107,34 +286,11 @@ impl App {
       }
       AppMessage::WindowClosing(_id) => {
         let mut task = Task::none();
-        let mut closing = true;
-
-        if closing && self.splits_edited {
-          let result = MessageDialog::new()
-            .set_title("Save Splits?")
-            .set_description("Splits haven't been saved. Would you like to save them?")
-            .set_buttons(MessageButtons::YesNoCancel)
-            .show();
-
-          match result {
-            MessageDialogResult::No => {}
-            MessageDialogResult::Yes => {
-              let result = rfd::FileDialog::new()
-                .add_filter("LiveSplit Splits", &["lss"])
-                .save_file();
-              if let Some(path) = result {
-                self.save_splits(path.to_string_lossy().to_string())?;
-                self.splits_edited = false;
-              }
-            }
-            MessageDialogResult::Cancel => {
-              closing = false;
-            }
-            _ => unreachable!(),
-          }
-        }
+        let closing = self.confirm_closing()?;
 
         if closing {
+          save_states(&self.lua_context.lua)
+            .unwrap_or_else(|err| error!("couldn't save component states: {}", err));
           task = task.chain(iced::exit());
         }
 
@@ -154,12 +310,20 @@ impl App {
         } = event
         {
           if let Some(translated_hotkey) = yast_windows::translate_event_to_hotkey(event)? {
-            for (action, hotkey) in &self.layout.hotkeys {
-              if *hotkey == translated_hotkey {
-                self.handle_hotkey(action.clone())?;
-                break;
+            let mut matched = Vec::new();
+            for timer_index in 0..=self.extra_timers.len() {
+              if let Some(hotkeys) = self.layout.timer_hotkeys(timer_index) {
+                for (action, hotkey) in hotkeys {
+                  if *hotkey == translated_hotkey {
+                    matched.push((timer_index, action.clone()));
+                    break;
+                  }
+                }
               }
             }
+            for (timer_index, action) in matched {
+              self.handle_hotkey(timer_index, action)?;
+            }
           }
         }
 
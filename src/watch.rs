@@ -0,0 +1,137 @@
+//! detection of external changes to the loaded splits and layout files
+
+use std::{
+  fs,
+  path::PathBuf,
+  time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use iced::Task;
+use livesplit_core::TimerPhase;
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult};
+
+use crate::{
+  App, AppMessage,
+  menu::{Menu, MenuMessage},
+};
+
+/// delay between two checks of the watched files
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// file loaded in YAST, with its last known modification time
+pub struct WatchedFile {
+  pub path: PathBuf,
+  modified: Option<SystemTime>,
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl WatchedFile {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    let path = path.into();
+    let modified = modified(&path);
+    Self { path, modified }
+  }
+
+  /// whether the file was modified since it was loaded or last acknowledged
+  fn changed(&self) -> bool {
+    match modified(&self.path) {
+      Some(m) => Some(m) != self.modified,
+      None => false,
+    }
+  }
+
+  /// takes the current version of the file as the known one
+  fn acknowledge(&mut self) {
+    self.modified = modified(&self.path);
+  }
+}
+
+pub struct FileWatcher {
+  pub splits: Option<WatchedFile>,
+  pub layout: Option<WatchedFile>,
+  last_check: Instant,
+}
+
+impl FileWatcher {
+  pub fn new() -> Self {
+    Self {
+      splits: None,
+      layout: None,
+      last_check: Instant::now(),
+    }
+  }
+}
+
+fn ask(title: &str, description: &str) -> bool {
+  let result = MessageDialog::new()
+    .set_title(title)
+    .set_description(description)
+    .set_buttons(MessageButtons::YesNo)
+    .show();
+  matches!(result, MessageDialogResult::Yes)
+}
+
+impl App {
+  /// path of the watched file if it changed, acknowledging the change
+  fn take_change(file: &mut Option<WatchedFile>) -> Option<String> {
+    let file = file.as_mut()?;
+    if !file.changed() {
+      return None;
+    }
+    file.acknowledge();
+    Some(file.path.to_string_lossy().to_string())
+  }
+
+  /// offers to reload the splits and layout files changed on disk, while the timer isn't running
+  pub fn check_watched_files(&mut self) -> Result<Task<AppMessage>> {
+    if self.watcher.last_check.elapsed() < CHECK_INTERVAL {
+      return Ok(Task::none());
+    }
+    self.watcher.last_check = Instant::now();
+
+    let phase = self
+      .active_timer()
+      .read()
+      .map_err(|_| anyhow::Error::msg("couldn't access timer"))?
+      .current_phase();
+    if phase != TimerPhase::NotRunning {
+      return Ok(Task::none());
+    }
+
+    let mut task = Task::none();
+
+    if let Some(path) = Self::take_change(&mut self.watcher.splits) {
+      let reload = if self.splits_edited {
+        ask(
+          "Splits Changed",
+          "The splits file was modified outside of YAST, but the loaded splits have unsaved changes. Reload it and discard them?",
+        )
+      } else {
+        ask(
+          "Splits Changed",
+          "The splits file was modified outside of YAST. Reload it?",
+        )
+      };
+      if reload {
+        // discarding the unsaved changes was already confirmed
+        self.splits_edited = false;
+        task = task.chain(Menu::update(self, MenuMessage::LoadSplits(path))?);
+      }
+    }
+
+    if let Some(path) = Self::take_change(&mut self.watcher.layout) {
+      if ask(
+        "Layout Changed",
+        "The layout file was modified outside of YAST. Reload it?",
+      ) {
+        task = task.chain(Menu::update(self, MenuMessage::LoadLayout(path))?);
+      }
+    }
+
+    Ok(task)
+  }
+}